@@ -0,0 +1,249 @@
+use chrono::{DateTime, Duration, Local, NaiveTime, TimeZone};
+use crate::record::{Record, RecordFieldType};
+use crate::journal::{Journal, JournalResult};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WorkingHours {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl Default for WorkingHours {
+    fn default() -> Self {
+        WorkingHours {
+            start: NaiveTime::from_hms_opt(9, 0, 0).unwrap_or_default(),
+            end: NaiveTime::from_hms_opt(17, 0, 0).unwrap_or_default(),
+        }
+    }
+}
+
+impl WorkingHours {
+    pub fn new(start: NaiveTime, end: NaiveTime) -> Self {
+        WorkingHours { start, end }
+    }
+
+    /// Splits the interval into the parts falling within working hours of each day.
+    pub fn clip(&self, from: DateTime<Local>, to: DateTime<Local>) -> Vec<(DateTime<Local>, DateTime<Local>)> {
+        let mut parts = Vec::new();
+        let mut date = from.naive_local().date();
+        while date <= to.naive_local().date() {
+            let bounds = (
+                Local.from_local_datetime(&date.and_time(self.start)).earliest(),
+                Local.from_local_datetime(&date.and_time(self.end)).earliest(),
+            );
+            if let (Some(day_start), Some(day_end)) = bounds {
+                let start = from.max(day_start);
+                let end = to.min(day_end);
+                if start < end {
+                    parts.push((start, end));
+                }
+            }
+            date = match date.succ_opt() {
+                Some(next) => next,
+                None => break,
+            };
+        }
+        parts
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Overlap {
+    pub first: Record,
+    pub second: Record,
+    pub duration: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Gap {
+    pub start: DateTime<Local>,
+    pub end: DateTime<Local>,
+}
+
+impl Gap {
+    pub fn duration(&self) -> Duration {
+        self.end - self.start
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Analysis {
+    pub overlaps: Vec<Overlap>,
+    pub gaps: Vec<Gap>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Analyzer {
+    working_hours: WorkingHours,
+    gap_threshold: Duration,
+}
+
+impl Default for Analyzer {
+    fn default() -> Self {
+        Analyzer {
+            working_hours: WorkingHours::default(),
+            gap_threshold: Duration::minutes(15),
+        }
+    }
+}
+
+impl Analyzer {
+    pub fn new() -> Self {
+        Analyzer::default()
+    }
+
+    pub fn with_working_hours(mut self, working_hours: WorkingHours) -> Self {
+        self.working_hours = working_hours;
+        self
+    }
+
+    pub fn with_gap_threshold(mut self, gap_threshold: Duration) -> Self {
+        self.gap_threshold = gap_threshold;
+        self
+    }
+
+    pub fn analyze<I: IntoIterator<Item = Record>>(&self, records: I) -> Analysis {
+        let mut records = records.into_iter()
+            .filter(|record| record.start.is_some())
+            .collect::<Vec<_>>();
+        records.sort_by_key(|record| record.start);
+
+        let mut analysis = Analysis::default();
+        for (idx, first) in records.iter().enumerate() {
+            let end = match first.end() {
+                Some(end) => end,
+                None => continue,
+            };
+            for second in records[idx + 1..].iter().take_while(|second| second.start < Some(end)) {
+                let overlap = second.end().map(|second_end| second_end.min(end)).unwrap_or(end)
+                    - second.start.unwrap_or(end);
+                if overlap > Duration::zero() {
+                    analysis.overlaps.push(Overlap { first: first.clone(), second: second.clone(), duration: overlap });
+                }
+            }
+        }
+
+        let mut covered_until: Option<DateTime<Local>> = None;
+        for record in &records {
+            if let (Some(from), Some(to)) = (covered_until, record.start) {
+                if from < to {
+                    analysis.gaps.extend(self.working_hours.clip(from, to)
+                        .into_iter()
+                        .map(|(start, end)| Gap { start, end })
+                        .filter(|gap| gap.duration() >= self.gap_threshold));
+                }
+            }
+            covered_until = covered_until.max(record.end());
+        }
+        analysis
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FixUp {
+    TrimOverlaps,
+    FillGaps(String),
+}
+
+fn trim(record: Record, overlap: Duration) -> Record {
+    let rest = record.rest.unwrap_or_else(Duration::zero).max(Duration::zero());
+    let from_rest = overlap.min(rest);
+    let from_activity = overlap - from_rest;
+    Record {
+        activity: record.activity.map(|activity| (activity - from_activity).max(Duration::zero())),
+        rest: record.rest.map(|rest| rest - from_rest),
+        ..record
+    }
+}
+
+impl Analysis {
+    /// Applies the fix-up to the journal and returns the number of changed or added records.
+    pub fn fix<J: Journal>(&self, journal: &mut J, fix_up: &FixUp) -> JournalResult<usize> {
+        let mut count = 0;
+        match fix_up {
+            FixUp::TrimOverlaps => {
+                let mut trimmed: Vec<(Record, Duration)> = Vec::new();
+                for overlap in &self.overlaps {
+                    match trimmed.iter_mut().find(|(record, _)| *record == overlap.first) {
+                        Some((_, duration)) => *duration = (*duration).max(overlap.duration),
+                        None => trimmed.push((overlap.first.clone(), overlap.duration)),
+                    }
+                }
+                for (record, duration) in trimmed {
+                    let query = [
+                        RecordFieldType::Start(record.start),
+                        RecordFieldType::Note(record.note.clone()),
+                    ];
+                    if journal.update(&query, None, |record| Some(trim(record, duration)))? {
+                        count += 1;
+                    }
+                }
+            },
+            FixUp::FillGaps(note) => {
+                for gap in &self.gaps {
+                    journal.add(&Record {
+                        start: Some(gap.start),
+                        activity: Some(gap.duration()),
+                        rest: None,
+                        note: note.clone(),
+                    })?;
+                    count += 1;
+                }
+            },
+        }
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::tests::record;
+
+    fn records() -> Vec<Record> {
+        vec![
+            record("2018-08-16 10:00:00", Some(60), Some(10), "Review"),
+            record("2018-08-16 09:00:00", Some(50), None, "Standup"),
+            record("2018-08-16 11:00:00", Some(30), None, "Call"),
+            record("2018-08-16 11:20:00", Some(5), None, "Email"),
+            record("2018-08-16 18:00:00", Some(30), None, "Late"),
+            record("2018-08-16 19:00:00", None, None, "Running"),
+        ]
+    }
+
+    #[test]
+    fn overlaps() {
+        let analysis = Analyzer::new().analyze(records());
+        assert_eq!(
+            vec![("Review", "Call", 10), ("Call", "Email", 5)],
+            analysis.overlaps.iter()
+                .map(|o| (o.first.note.as_str(), o.second.note.as_str(), o.duration.num_minutes()))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn gaps() {
+        let analysis = Analyzer::new().analyze(records());
+        let gaps = analysis.gaps.iter()
+            .map(|gap| (gap.start.format("%H:%M").to_string(), gap.end.format("%H:%M").to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(vec![("11:30".to_string(), "17:00".to_string())], gaps);
+
+        let analysis = Analyzer::new()
+            .with_gap_threshold(Duration::minutes(5))
+            .with_working_hours(WorkingHours::new(
+                NaiveTime::from_hms_opt(8, 0, 0).unwrap(),
+                NaiveTime::from_hms_opt(20, 0, 0).unwrap(),
+            ))
+            .analyze(records());
+        assert_eq!(vec![10, 390, 30], analysis.gaps.iter().map(|gap| gap.duration().num_minutes()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn trim_record() {
+        let trimmed = trim(record("2018-08-16 10:00:00", Some(60), Some(10), "Review"), Duration::minutes(15));
+        assert_eq!(Some(Duration::minutes(55)), trimmed.activity);
+        assert_eq!(Some(Duration::zero()), trimmed.rest);
+    }
+}
@@ -0,0 +1,247 @@
+use std::fmt;
+use std::io::Write;
+use chrono::Duration;
+use crate::record::{Record, RecordMatcher};
+use crate::journal::JournalResult;
+use crate::rounding::RoundingPolicy;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rate {
+    pub client: String,
+    pub item: String,
+    /// Hourly rate in minor currency units, e.g. cents.
+    pub hourly: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineItem {
+    pub client: String,
+    pub item: String,
+    pub entries: usize,
+    pub quantity: Duration,
+    pub adjustment: Duration,
+    pub rate: i64,
+    pub amount: i64,
+}
+
+impl LineItem {
+    pub fn hours(&self) -> String {
+        format!("{:.2}", self.quantity.num_minutes() as f64 / 60.0)
+    }
+}
+
+pub fn format_amount(amount: i64) -> String {
+    let sign = if amount < 0 { "-" } else { "" };
+    format!("{}{}.{:02}", sign, amount.abs() / 100, amount.abs() % 100)
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Invoice {
+    pub items: Vec<LineItem>,
+    pub unbilled: usize,
+}
+
+impl Invoice {
+    pub fn total(&self) -> i64 {
+        self.items.iter().map(|item| item.amount).sum()
+    }
+
+    pub fn write_csv<W: Write>(&self, writer: W) -> JournalResult {
+        let mut writer = ::csv::Writer::from_writer(writer);
+        writer.write_record(["Client", "Item", "Entries", "Hours", "Rate", "Amount"])?;
+        for item in &self.items {
+            writer.write_record(&[
+                item.client.clone(),
+                item.item.clone(),
+                item.entries.to_string(),
+                item.hours(),
+                format_amount(item.rate),
+                format_amount(item.amount),
+            ])?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for Invoice {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let client_width = self.items.iter().map(|item| item.client.len()).chain(Some(6)).max().unwrap_or_default();
+        let item_width = self.items.iter().map(|item| item.item.len()).chain(Some(4)).max().unwrap_or_default();
+
+        writeln!(f, "{:<cw$}  {:<iw$}  {:>8}  {:>10}  {:>12}",
+                 "Client", "Item", "Hours", "Rate", "Amount", cw = client_width, iw = item_width)?;
+        for item in &self.items {
+            writeln!(f, "{:<cw$}  {:<iw$}  {:>8}  {:>10}  {:>12}",
+                     item.client, item.item, item.hours(), format_amount(item.rate), format_amount(item.amount),
+                     cw = client_width, iw = item_width)?;
+        }
+        writeln!(f, "{:<w$}  {:>12}", "Total", format_amount(self.total()), w = client_width + item_width + 24)
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Billing {
+    rates: Vec<(RecordMatcher, Rate)>,
+    filter: Option<RecordMatcher>,
+    rounding: RoundingPolicy,
+}
+
+impl Billing {
+    pub fn new() -> Self {
+        Billing::default()
+    }
+
+    pub fn rate(mut self, matcher: RecordMatcher, client: &str, hourly: i64) -> Self {
+        let item = match &matcher {
+            RecordMatcher::Tag(tag) => format!("{}{}", Record::TAG_PREFIX, tag),
+            RecordMatcher::Project(project) => project.clone(),
+        };
+        self.rates.push((matcher, Rate { client: client.to_string(), item, hourly }));
+        self
+    }
+
+    pub fn project_rate(self, project: &str, client: &str, hourly: i64) -> Self {
+        self.rate(RecordMatcher::Project(project.to_string()), client, hourly)
+    }
+
+    pub fn tag_rate(self, tag: &str, client: &str, hourly: i64) -> Self {
+        self.rate(RecordMatcher::Tag(tag.to_string()), client, hourly)
+    }
+
+    pub fn with_filter(mut self, filter: RecordMatcher) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    pub fn with_rounding(mut self, rounding: RoundingPolicy) -> Self {
+        self.rounding = rounding;
+        self
+    }
+
+    pub fn invoice<I: IntoIterator<Item = Record>>(&self, records: I) -> Invoice {
+        let mut invoice = Invoice::default();
+        let mut items = self.rates.iter()
+            .map(|(_, rate)| LineItem {
+                client: rate.client.clone(),
+                item: rate.item.clone(),
+                entries: 0,
+                quantity: Duration::zero(),
+                adjustment: Duration::zero(),
+                rate: rate.hourly,
+                amount: 0,
+            })
+            .collect::<Vec<_>>();
+        let mut billed = self.rates.iter().map(|_| Vec::new()).collect::<Vec<_>>();
+
+        for record in records {
+            if !self.filter.as_ref().map(|filter| filter.matches(&record)).unwrap_or(true) {
+                continue;
+            }
+            match record.activity {
+                Some(activity) if activity > Duration::zero() => (),
+                _ => continue,
+            }
+            match self.rates.iter().position(|(matcher, _)| matcher.matches(&record)) {
+                Some(idx) => billed[idx].push(record),
+                None => invoice.unbilled += 1,
+            }
+        }
+
+        for (item, records) in items.iter_mut().zip(billed) {
+            let rounded = self.rounding.rounded_activity(&records);
+            item.entries = records.len();
+            item.quantity = rounded.rounded;
+            item.adjustment = rounded.adjustment();
+            let minutes = item.quantity.num_minutes();
+            item.amount = (minutes * item.rate + 30).div_euclid(60);
+        }
+        invoice.items = items.into_iter().filter(|item| item.entries > 0).collect();
+        invoice
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::tests::record;
+
+    fn records() -> Vec<Record> {
+        vec![
+            record("2018-08-16 09:00:00", Some(50), None, "Review @tt-core"),
+            record("2018-08-16 10:00:00", Some(7), Some(3), "Fix @tt-core #urgent"),
+            record("2018-08-16 11:00:00", Some(30), None, "Call #support"),
+            record("2018-08-16 12:00:00", Some(20), None, "Lunch"),
+            record("2018-08-16 13:00:00", None, None, "Running @tt-core"),
+        ]
+    }
+
+    #[test]
+    fn invoice() {
+        let billing = Billing::new()
+            .project_rate("tt-core", "ACME", 6000)
+            .tag_rate("support", "Globex", 4000)
+            .with_rounding(RoundingPolicy::up(15));
+        let invoice = billing.invoice(records());
+
+        assert_eq!(vec![
+            LineItem {
+                client: "ACME".to_string(),
+                item: "tt-core".to_string(),
+                entries: 2,
+                quantity: Duration::minutes(75),
+                adjustment: Duration::minutes(18),
+                rate: 6000,
+                amount: 7500,
+            },
+            LineItem {
+                client: "Globex".to_string(),
+                item: "#support".to_string(),
+                entries: 1,
+                quantity: Duration::minutes(30),
+                adjustment: Duration::zero(),
+                rate: 4000,
+                amount: 2000,
+            },
+        ], invoice.items);
+        assert_eq!(1, invoice.unbilled);
+        assert_eq!(9500, invoice.total());
+
+        assert_eq!("Client  Item         Hours        Rate        Amount
+ACME    tt-core       1.25       60.00         75.00
+Globex  #support      0.50       40.00         20.00
+Total                                          95.00
+", invoice.to_string());
+
+        let mut csv = Vec::new();
+        invoice.write_csv(&mut csv).unwrap();
+        assert_eq!("Client,Item,Entries,Hours,Rate,Amount
+ACME,tt-core,2,1.25,60.00,75.00
+Globex,#support,1,0.50,40.00,20.00
+", String::from_utf8(csv).unwrap());
+    }
+
+    #[test]
+    fn filtered_invoice() {
+        let billing = Billing::new()
+            .project_rate("tt-core", "ACME", 6000)
+            .with_filter(RecordMatcher::Tag("urgent".to_string()));
+        let invoice = billing.invoice(records());
+        assert_eq!(1, invoice.items.len());
+        assert_eq!(Duration::minutes(7), invoice.items[0].quantity);
+        assert_eq!(700, invoice.items[0].amount);
+        assert_eq!(0, invoice.unbilled);
+    }
+
+    #[test]
+    fn daily_rounding() {
+        let billing = Billing::new()
+            .project_rate("tt-core", "ACME", 6000)
+            .with_rounding(RoundingPolicy::up(15).per_day());
+        let invoice = billing.invoice(records());
+        assert_eq!(2, invoice.items[0].entries);
+        assert_eq!(Duration::minutes(60), invoice.items[0].quantity);
+        assert_eq!(Duration::minutes(3), invoice.items[0].adjustment);
+        assert_eq!(6000, invoice.total());
+    }
+}
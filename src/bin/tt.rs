@@ -0,0 +1,369 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, TimeZone};
+use failure::Fail;
+use tt_core::{
+    journal::{Journal, JournalResult, file::FileJournal},
+    record::{Record, RecordFieldType},
+    report::{markdown, org},
+};
+
+const USAGE: &str = "\
+Usage: tt [--journal PATH] <command> [options] [note]
+
+Commands:
+    start [--at TIME] [note]      stop the running record and start a new one
+    stop [--at TIME] [query]      stop the running record
+    pause [--at TIME] [query]     stop counting activity of the running record
+    resume [--at TIME] [query]    count the pause as rest and continue the record
+    add --start TIME [--activity MIN] [--rest MIN] [note]
+    edit [query] [--set-start TIME] [--set-activity MIN] [--set-rest MIN] [--set-note NOTE]
+    rm [query]
+    last
+    list [--from DATE] [--to DATE]
+    report [--from DATE] [--to DATE] [--format markdown|org]
+
+Query options select a record like `go_to_record`, by default the last one:
+    --start TIME  --activity MIN  --rest MIN  --note NOTE  --offset N
+
+The journal path is taken from --journal, the TT_JOURNAL environment variable
+or the `journal = PATH` line of $XDG_CONFIG_HOME/tt/config (~/.config/tt/config).";
+
+#[derive(Debug, Fail)]
+enum CliError {
+    #[fail(display = "{}", _0)]
+    Usage(String),
+    #[fail(display = "no record matches the query")]
+    RecordNotFound,
+    #[fail(display = "the record is not running")]
+    NotRunning,
+    #[fail(display = "the record is not paused")]
+    NotPaused,
+}
+
+fn usage<T, S: Into<String>>(message: S) -> JournalResult<T> {
+    Err(CliError::Usage(message.into()).into())
+}
+
+#[derive(Debug, Default)]
+struct Args {
+    command: Option<String>,
+    options: Vec<(String, String)>,
+    words: Vec<String>,
+}
+
+impl Args {
+    fn parse<I: IntoIterator<Item = String>>(args: I) -> JournalResult<Self> {
+        let mut parsed = Args::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            if let Some(name) = arg.strip_prefix("--") {
+                match args.next() {
+                    Some(value) => parsed.options.push((name.to_string(), value)),
+                    None => return usage(format!("missing value of `{}`", arg)),
+                }
+            } else if arg == "-j" {
+                match args.next() {
+                    Some(value) => parsed.options.push(("journal".to_string(), value)),
+                    None => return usage("missing value of `-j`"),
+                }
+            } else if parsed.command.is_none() {
+                parsed.command = Some(arg);
+            } else {
+                parsed.words.push(arg);
+            }
+        }
+        Ok(parsed)
+    }
+
+    fn option(&self, name: &str) -> Option<&str> {
+        self.options.iter().rev().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+    }
+
+    fn note(&self) -> String {
+        self.words.join(" ")
+    }
+
+    fn check_options(&self, allowed: &[&str]) -> JournalResult {
+        match self.options.iter().find(|(key, _)| key != "journal" && !allowed.contains(&key.as_str())) {
+            Some((key, _)) => usage(format!("unexpected option `--{}`", key)),
+            None => Ok(()),
+        }
+    }
+
+    /// Rejects positional words for commands that don't take a note.
+    fn check_no_words(&self) -> JournalResult {
+        match self.words.first() {
+            Some(word) => usage(format!("unexpected argument `{}`", word)),
+            None => Ok(()),
+        }
+    }
+
+    fn time(&self, name: &str) -> JournalResult<Option<DateTime<Local>>> {
+        self.option(name).map(parse_time).transpose()
+    }
+
+    fn minutes(&self, name: &str) -> JournalResult<Option<Duration>> {
+        self.option(name).map(parse_minutes).transpose()
+    }
+
+    fn date(&self, name: &str) -> JournalResult<Option<NaiveDate>> {
+        self.option(name)
+            .map(|value| NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .or_else(|_| usage(format!("can't parse date `{}`", value))))
+            .transpose()
+    }
+
+    fn at(&self) -> JournalResult<DateTime<Local>> {
+        Ok(self.time("at")?.unwrap_or_else(|| Record::now().start.unwrap_or_else(Local::now)))
+    }
+
+    /// The query and offset given by the query options; the last record when there are none.
+    fn query(&self) -> JournalResult<(Vec<RecordFieldType>, Option<i32>)> {
+        let mut query = Vec::new();
+        if let Some(start) = self.time("start")? {
+            query.push(RecordFieldType::Start(Some(start)));
+        }
+        if let Some(activity) = self.minutes("activity")? {
+            query.push(RecordFieldType::Activity(Some(activity)));
+        }
+        if let Some(rest) = self.minutes("rest")? {
+            query.push(RecordFieldType::Rest(Some(rest)));
+        }
+        if let Some(note) = self.option("note") {
+            query.push(RecordFieldType::Note(note.to_string()));
+        }
+        let offset = match self.option("offset") {
+            Some(offset) => Some(offset.parse::<i32>().or_else(|_| usage(format!("can't parse offset `{}`", offset)))?),
+            None if query.is_empty() => Some(-1),
+            None => None,
+        };
+        Ok((query, offset))
+    }
+}
+
+fn parse_time(source: &str) -> JournalResult<DateTime<Local>> {
+    let naive = NaiveDateTime::parse_from_str(source, Record::START_DATETIME_FORMAT)
+        .or_else(|_| NaiveDateTime::parse_from_str(source, "%Y-%m-%d %H:%M"))
+        .or_else(|_| NaiveDateTime::parse_from_str(&format!("{} {}", Local::now().format("%Y-%m-%d"), source), "%Y-%m-%d %H:%M"));
+    match naive.ok().and_then(|naive| Local.from_local_datetime(&naive).earliest()) {
+        Some(time) => Ok(time),
+        None => usage(format!("can't parse time `{}`", source)),
+    }
+}
+
+fn parse_minutes(source: &str) -> JournalResult<Duration> {
+    source.parse::<i64>()
+        .map(Duration::minutes)
+        .or_else(|_| usage(format!("can't parse minutes `{}`", source)))
+}
+
+fn config_path() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .map(|dir| dir.join("tt").join("config"))
+}
+
+fn journal_path(args: &Args) -> JournalResult<PathBuf> {
+    if let Some(path) = args.option("journal") {
+        return Ok(PathBuf::from(path));
+    }
+    if let Some(path) = env::var_os("TT_JOURNAL") {
+        return Ok(PathBuf::from(path));
+    }
+    if let Some(config) = config_path().filter(|config| config.exists()) {
+        let content = fs::read_to_string(&config)?;
+        let path = content.lines()
+            .map(str::trim)
+            .filter(|line| !line.starts_with('#'))
+            .filter_map(|line| {
+                let mut parts = line.splitn(2, '=');
+                match (parts.next().map(str::trim), parts.next()) {
+                    (Some("journal"), Some(path)) => Some(PathBuf::from(path.trim())),
+                    _ => None,
+                }
+            })
+            .next_back();
+        if let Some(path) = path {
+            return Ok(path);
+        }
+    }
+    usage("the journal path is not set, use --journal, TT_JOURNAL or the config file")
+}
+
+fn stopped(record: Record, at: DateTime<Local>) -> Record {
+    let activity = record.start.map(|start| at - start - record.rest.unwrap_or_else(Duration::zero));
+    Record { activity, ..record }
+}
+
+/// Updates the selected record and returns its new value.
+fn update<F>(journal: &mut FileJournal, args: &Args, f: F) -> JournalResult<Record>
+    where F: FnOnce(Record) -> JournalResult<Record>,
+{
+    let (query, offset) = args.query()?;
+    let record = journal.get(&query, offset)?.ok_or(CliError::RecordNotFound)?;
+    let updated = f(record)?;
+    journal.update(&query, offset, |_| Some(updated.clone()))?;
+    Ok(updated)
+}
+
+/// The last record, or none while the journal file doesn't exist yet.
+fn last_record(journal: &FileJournal) -> JournalResult<Option<Record>> {
+    if Path::new(journal.path()).exists() {
+        journal.get(&[], Some(-1))
+    } else {
+        Ok(None)
+    }
+}
+
+/// All records, or none while the journal file doesn't exist yet.
+fn records(journal: &FileJournal) -> JournalResult<Vec<Record>> {
+    if Path::new(journal.path()).exists() {
+        journal.records()
+    } else {
+        Ok(Vec::new())
+    }
+}
+
+fn in_range(record: &Record, from: Option<NaiveDate>, to: Option<NaiveDate>) -> bool {
+    let date = record.start.map(|start| start.naive_local().date());
+    from.map(|from| date >= Some(from)).unwrap_or(true) && to.map(|to| date.map(|date| date <= to).unwrap_or(false)).unwrap_or(true)
+}
+
+fn run(args: Args) -> JournalResult {
+    let command = match args.command.as_deref() {
+        Some("help") | None => {
+            println!("{}", USAGE);
+            return Ok(());
+        },
+        Some(command) => command,
+    };
+    let mut journal = FileJournal::open(journal_path(&args)?)?;
+
+    match command {
+        "start" => {
+            args.check_options(&["at"])?;
+            let at = args.at()?;
+            if let Some(last) = last_record(&journal)? {
+                if last.start.is_some() && last.activity.is_none() {
+                    journal.update(&[], Some(-1), |record| Some(stopped(record, at)))?;
+                }
+            }
+            let record = Record { start: Some(at), activity: None, rest: None, note: args.note() };
+            journal.add(&record)?;
+            println!("{}", record.to_string());
+        },
+        "stop" | "pause" => {
+            args.check_options(&["at", "start", "activity", "rest", "note", "offset"])?;
+            args.check_no_words()?;
+            let at = args.at()?;
+            let pause = command == "pause";
+            let record = update(&mut journal, &args, |record| {
+                if record.activity.is_some() {
+                    return if pause { Err(CliError::NotRunning.into()) } else { Ok(record) };
+                }
+                Ok(stopped(record, at))
+            })?;
+            println!("{}", record.to_string());
+        },
+        "resume" => {
+            args.check_options(&["at", "start", "activity", "rest", "note", "offset"])?;
+            args.check_no_words()?;
+            let at = args.at()?;
+            let record = update(&mut journal, &args, |record| {
+                let (start, activity) = match (record.start, record.activity) {
+                    (Some(start), Some(activity)) => (start, activity),
+                    _ => return Err(CliError::NotPaused.into()),
+                };
+                Ok(Record { activity: None, rest: Some(at - start - activity), ..record })
+            })?;
+            println!("{}", record.to_string());
+        },
+        "add" => {
+            args.check_options(&["start", "activity", "rest"])?;
+            let start = match args.time("start")? {
+                Some(start) => start,
+                None => return usage("`add` requires `--start`"),
+            };
+            let record = Record {
+                start: Some(start),
+                activity: args.minutes("activity")?,
+                rest: args.minutes("rest")?,
+                note: args.note(),
+            };
+            journal.add(&record)?;
+            println!("{}", record.to_string());
+        },
+        "edit" => {
+            args.check_options(&["start", "activity", "rest", "note", "offset", "set-start", "set-activity", "set-rest", "set-note"])?;
+            args.check_no_words()?;
+            let start = args.time("set-start")?;
+            let activity = args.minutes("set-activity")?;
+            let rest = args.minutes("set-rest")?;
+            let note = args.option("set-note").map(str::to_string);
+            let record = update(&mut journal, &args, |record| Ok(Record {
+                start: start.or(record.start),
+                activity: activity.or(record.activity),
+                rest: rest.or(record.rest),
+                note: note.unwrap_or(record.note),
+            }))?;
+            println!("{}", record.to_string());
+        },
+        "rm" => {
+            args.check_options(&["start", "activity", "rest", "note", "offset"])?;
+            args.check_no_words()?;
+            let (query, offset) = args.query()?;
+            let mut removed = None;
+            journal.remove(&query, offset, |record| {
+                removed = Some(record);
+                true
+            })?;
+            match removed {
+                Some(record) => println!("{}", record.to_string()),
+                None => return Err(CliError::RecordNotFound.into()),
+            }
+        },
+        "last" => {
+            args.check_options(&[])?;
+            args.check_no_words()?;
+            match last_record(&journal)? {
+                Some(record) => println!("{}", record.to_string()),
+                None => return Err(CliError::RecordNotFound.into()),
+            }
+        },
+        "list" => {
+            args.check_options(&["from", "to"])?;
+            args.check_no_words()?;
+            let (from, to) = (args.date("from")?, args.date("to")?);
+            for record in records(&journal)?.into_iter().filter(|record| in_range(record, from, to)) {
+                println!("{}", record.to_string());
+            }
+        },
+        "report" => {
+            args.check_options(&["from", "to", "format"])?;
+            args.check_no_words()?;
+            let (from, to) = (args.date("from")?, args.date("to")?);
+            let records = records(&journal)?.into_iter().filter(|record| in_range(record, from, to));
+            match args.option("format").unwrap_or("markdown") {
+                "markdown" => print!("{}", markdown::render(records)),
+                "org" => print!("{}", org::render(records)),
+                format => return usage(format!("unknown report format `{}`", format)),
+            }
+        },
+        command => return usage(format!("unknown command `{}`", command)),
+    }
+    Ok(())
+}
+
+fn main() {
+    if let Err(err) = Args::parse(env::args().skip(1)).and_then(run) {
+        eprintln!("tt: {}", err);
+        if let Some(CliError::Usage(_)) = err.downcast_ref::<CliError>() {
+            eprintln!("\n{}", USAGE);
+        }
+        process::exit(1);
+    }
+}
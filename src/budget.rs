@@ -0,0 +1,303 @@
+use std::fmt;
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate};
+use crate::record::{Record, RecordMatcher};
+use crate::journal::{Journal, JournalResult};
+use crate::report::format_duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Period {
+    Day,
+    Week,
+    Month,
+}
+
+impl Period {
+    /// First day of the period containing `date` and first day of the next one.
+    pub fn bounds(&self, date: NaiveDate) -> (NaiveDate, NaiveDate) {
+        match self {
+            Period::Day => (date, date + Duration::days(1)),
+            Period::Week => {
+                let from = date - Duration::days(date.weekday().num_days_from_monday() as i64);
+                (from, from + Duration::days(7))
+            },
+            Period::Month => {
+                let from = date.with_day(1).unwrap_or(date);
+                let to = if from.month() == 12 {
+                    NaiveDate::from_ymd_opt(from.year() + 1, 1, 1)
+                } else {
+                    NaiveDate::from_ymd_opt(from.year(), from.month() + 1, 1)
+                };
+                (from, to.unwrap_or(from))
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    AtMost(Duration),
+    AtLeast(Duration),
+}
+
+impl Limit {
+    pub fn target(&self) -> Duration {
+        match *self {
+            Limit::AtMost(target) | Limit::AtLeast(target) => target,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Budget {
+    pub name: String,
+    pub matcher: RecordMatcher,
+    pub period: Period,
+    pub limit: Limit,
+}
+
+impl Budget {
+    pub fn new(matcher: RecordMatcher, period: Period, limit: Limit) -> Self {
+        let name = match &matcher {
+            RecordMatcher::Tag(tag) => format!("{}{}", Record::TAG_PREFIX, tag),
+            RecordMatcher::Project(project) => format!("{}{}", Record::PROJECT_PREFIX, project),
+        };
+        Budget { name, matcher, period, limit }
+    }
+
+    pub fn at_most(matcher: RecordMatcher, period: Period, max: Duration) -> Self {
+        Budget::new(matcher, period, Limit::AtMost(max))
+    }
+
+    pub fn at_least(matcher: RecordMatcher, period: Period, min: Duration) -> Self {
+        Budget::new(matcher, period, Limit::AtLeast(min))
+    }
+
+    pub fn with_name<S: Into<String>>(mut self, name: S) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Sums the activity of matching records in the period containing `now`;
+    /// a running record counts until `now`.
+    pub fn evaluate<'a, I>(&self, records: I, now: DateTime<Local>) -> Progress
+        where I: IntoIterator<Item = &'a Record>,
+    {
+        let (from, to) = self.period.bounds(now.naive_local().date());
+        let spent = records.into_iter()
+            .filter(|record| self.matcher.matches(record))
+            .filter_map(|record| {
+                let start = record.start?;
+                let date = start.naive_local().date();
+                if date < from || date >= to {
+                    return None;
+                }
+                record.activity.or_else(|| {
+                    Some((now - start - record.rest.unwrap_or_else(Duration::zero)).max(Duration::zero()))
+                })
+            })
+            .fold(Duration::zero(), |sum, activity| sum + activity);
+        Progress { name: self.name.clone(), limit: self.limit, from, to, spent }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Balance {
+    Remaining(Duration),
+    Exceeded(Duration),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Progress {
+    pub name: String,
+    pub limit: Limit,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub spent: Duration,
+}
+
+impl Progress {
+    pub fn balance(&self) -> Balance {
+        let target = self.limit.target();
+        if self.spent > target {
+            Balance::Exceeded(self.spent - target)
+        } else {
+            Balance::Remaining(target - self.spent)
+        }
+    }
+
+    /// Spent time in percent of the target.
+    pub fn percent(&self) -> i64 {
+        match self.limit.target().num_minutes() {
+            0 => 100,
+            target => self.spent.num_minutes() * 100 / target,
+        }
+    }
+
+    /// Activity expected by `now` when the target is spread evenly over the period.
+    pub fn expected(&self, now: DateTime<Local>) -> Duration {
+        let from = self.from.and_hms_opt(0, 0, 0).unwrap_or_default();
+        let to = self.to.and_hms_opt(0, 0, 0).unwrap_or_default();
+        let total = (to - from).num_seconds().max(1);
+        let elapsed = (now.naive_local() - from).num_seconds().max(0).min(total);
+        Duration::seconds(self.limit.target().num_seconds() * elapsed / total)
+    }
+}
+
+impl fmt::Display for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let limit = match self.limit {
+            Limit::AtMost(_) => "at most",
+            Limit::AtLeast(_) => "at least",
+        };
+        write!(f, "{}: {} of {} {}", self.name, format_duration(self.spent), limit, format_duration(self.limit.target()))?;
+        match self.balance() {
+            Balance::Remaining(remaining) => write!(f, ", {} remaining", format_duration(remaining)),
+            Balance::Exceeded(exceeded) => write!(f, ", exceeded by {}", format_duration(exceeded)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertKind {
+    /// A maximum is exceeded.
+    Exceeded,
+    /// A maximum is almost reached.
+    Approaching,
+    /// A minimum is behind the even pace for the period.
+    Behind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alert {
+    pub kind: AlertKind,
+    pub progress: Progress,
+}
+
+impl fmt::Display for Alert {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match self.kind {
+            AlertKind::Exceeded => "exceeded",
+            AlertKind::Approaching => "approaching",
+            AlertKind::Behind => "behind",
+        };
+        write!(f, "[{}] {}", kind, self.progress)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Budgets {
+    budgets: Vec<Budget>,
+    warning_percent: i64,
+    reported: Vec<(usize, NaiveDate, AlertKind)>,
+}
+
+impl Default for Budgets {
+    fn default() -> Self {
+        Budgets {
+            budgets: Vec::new(),
+            warning_percent: 80,
+            reported: Vec::new(),
+        }
+    }
+}
+
+impl Budgets {
+    pub fn new() -> Self {
+        Budgets::default()
+    }
+
+    pub fn with_budget(mut self, budget: Budget) -> Self {
+        self.budgets.push(budget);
+        self
+    }
+
+    pub fn with_warning_percent(mut self, warning_percent: i64) -> Self {
+        self.warning_percent = warning_percent;
+        self
+    }
+
+    pub fn budgets(&self) -> &[Budget] {
+        &self.budgets
+    }
+
+    pub fn progress<J: Journal>(&self, journal: &J, now: DateTime<Local>) -> JournalResult<Vec<Progress>> {
+        let records = journal.records()?;
+        Ok(self.budgets.iter().map(|budget| budget.evaluate(&records, now)).collect())
+    }
+
+    pub fn alerts<J: Journal>(&self, journal: &J, now: DateTime<Local>) -> JournalResult<Vec<Alert>> {
+        Ok(self.progress(journal, now)?
+            .into_iter()
+            .filter_map(|progress| self.alert_kind(&progress, now).map(|kind| Alert { kind, progress }))
+            .collect())
+    }
+
+    fn alert_kind(&self, progress: &Progress, now: DateTime<Local>) -> Option<AlertKind> {
+        match progress.limit {
+            Limit::AtMost(max) if progress.spent > max => Some(AlertKind::Exceeded),
+            Limit::AtMost(_) if progress.percent() >= self.warning_percent => Some(AlertKind::Approaching),
+            Limit::AtLeast(_) if progress.spent < progress.expected(now) => Some(AlertKind::Behind),
+            _ => None,
+        }
+    }
+
+    /// Returns the alerts not returned yet for the same budget, period and kind.
+    /// Alerts of past periods are forgotten.
+    pub fn poll<J: Journal>(&mut self, journal: &J, now: DateTime<Local>) -> JournalResult<Vec<Alert>> {
+        let progress = self.progress(journal, now)?;
+        self.reported.retain(|(idx, from, _)| progress.get(*idx).map(|progress| progress.from == *from).unwrap_or(false));
+
+        let mut alerts = Vec::new();
+        for (idx, progress) in progress.into_iter().enumerate() {
+            if let Some(kind) = self.alert_kind(&progress, now) {
+                let key = (idx, progress.from, kind);
+                if !self.reported.contains(&key) {
+                    self.reported.push(key);
+                    alerts.push(Alert { kind, progress });
+                }
+            }
+        }
+        Ok(alerts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::tests::record;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn bounds() {
+        assert_eq!((date(2018, 8, 16), date(2018, 8, 17)), Period::Day.bounds(date(2018, 8, 16)));
+        assert_eq!((date(2018, 8, 13), date(2018, 8, 20)), Period::Week.bounds(date(2018, 8, 16)));
+        assert_eq!((date(2018, 8, 13), date(2018, 8, 20)), Period::Week.bounds(date(2018, 8, 19)));
+        assert_eq!((date(2018, 8, 1), date(2018, 9, 1)), Period::Month.bounds(date(2018, 8, 16)));
+        assert_eq!((date(2018, 12, 1), date(2019, 1, 1)), Period::Month.bounds(date(2018, 12, 31)));
+    }
+
+    #[test]
+    fn evaluate() {
+        let records = vec![
+            record("2018-08-12 10:00:00", Some(60), None, "Retro #meeting"),
+            record("2018-08-13 10:00:00", Some(90), None, "Planning #meeting"),
+            record("2018-08-14 10:00:00", Some(30), None, "Code @tt"),
+            record("2018-08-16 10:00:00", None, Some(10), "Sync #meeting"),
+        ];
+        let now = record("2018-08-16 11:00:00", None, None, "").start.unwrap();
+        let budget = Budget::at_most(RecordMatcher::Tag("meeting".to_string()), Period::Week, Duration::hours(2));
+        let progress = budget.evaluate(&records, now);
+        assert_eq!("#meeting", progress.name);
+        assert_eq!(Duration::minutes(140), progress.spent);
+        assert_eq!(Balance::Exceeded(Duration::minutes(20)), progress.balance());
+        assert_eq!("#meeting: 2:20 of at most 2:00, exceeded by 0:20", progress.to_string());
+
+        let budget = Budget::at_least(RecordMatcher::Project("tt".to_string()), Period::Month, Duration::hours(20));
+        let progress = budget.evaluate(&records, now);
+        assert_eq!(Balance::Remaining(Duration::minutes(1170)), progress.balance());
+        assert_eq!(2, progress.percent());
+    }
+}
@@ -0,0 +1,137 @@
+pub mod csv;
+pub mod ical;
+pub mod timeclock;
+pub mod timewarrior;
+pub mod toggl;
+#[cfg(feature = "json")]
+pub mod watson;
+
+use std::fmt;
+use chrono::Duration;
+use crate::record::Record;
+use crate::journal::{Journal, JournalResult};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IssueKind {
+    Skipped,
+    Lossy,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportIssue {
+    pub position: usize,
+    pub kind: IssueKind,
+    pub message: String,
+}
+
+impl fmt::Display for ImportIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match self.kind {
+            IssueKind::Skipped => "skipped",
+            IssueKind::Lossy => "lossy",
+        };
+        write!(f, "#{} {}: {}", self.position, kind, self.message)
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Import {
+    pub records: Vec<Record>,
+    pub issues: Vec<ImportIssue>,
+}
+
+impl Import {
+    pub fn skip<S: Into<String>>(&mut self, position: usize, message: S) {
+        self.issues.push(ImportIssue { position, kind: IssueKind::Skipped, message: message.into() });
+    }
+
+    pub fn lossy<S: Into<String>>(&mut self, position: usize, message: S) {
+        self.issues.push(ImportIssue { position, kind: IssueKind::Lossy, message: message.into() });
+    }
+
+    pub fn skipped(&self) -> impl Iterator<Item = &ImportIssue> {
+        self.issues.iter().filter(|issue| issue.kind == IssueKind::Skipped)
+    }
+
+    pub fn lossy_entries(&self) -> impl Iterator<Item = &ImportIssue> {
+        self.issues.iter().filter(|issue| issue.kind == IssueKind::Lossy)
+    }
+
+    pub fn write_into<J: Journal>(&self, journal: &mut J) -> JournalResult<usize> {
+        for record in &self.records {
+            journal.add(record)?;
+        }
+        Ok(self.records.len())
+    }
+}
+
+/// Journal formats keep whole minutes of activity only.
+pub(crate) fn report_seconds(import: &mut Import, position: usize, activity: Option<Duration>) {
+    if let Some(activity) = activity {
+        let seconds = activity.num_seconds() % 60;
+        if seconds != 0 || activity.subsec_nanos() != 0 {
+            import.lossy(position, format!("{} s of activity beyond whole minutes dropped", seconds.abs()));
+        }
+    }
+}
+
+pub(crate) fn note_word(prefix: char, word: &str) -> (String, bool) {
+    let words = word.split_whitespace().collect::<Vec<_>>();
+    (format!("{}{}", prefix, words.join("_")), words.len() != 1 || words[0] != word)
+}
+
+pub(crate) fn compose_note(
+    import: &mut Import,
+    position: usize,
+    description: &str,
+    project: Option<&str>,
+    tags: &[String],
+) -> String {
+    let mut words = Vec::new();
+    let description = description.trim();
+    if !description.is_empty() {
+        words.push(description.replace(&['\r', '\n'][..], " "));
+        if description.contains(&['\r', '\n'][..]) {
+            import.lossy(position, "line breaks in description replaced by spaces");
+        }
+    }
+    if let Some(project) = project.map(str::trim).filter(|project| !project.is_empty()) {
+        let (word, changed) = note_word(Record::PROJECT_PREFIX, project);
+        if changed {
+            import.lossy(position, format!("project `{}` stored as `{}`", project, word));
+        }
+        words.push(word);
+    }
+    for tag in tags.iter().map(|tag| tag.trim()).filter(|tag| !tag.is_empty()) {
+        let (word, changed) = note_word(Record::TAG_PREFIX, tag);
+        if changed {
+            import.lossy(position, format!("tag `{}` stored as `{}`", tag, word));
+        }
+        words.push(word);
+    }
+    words.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compose() {
+        let mut import = Import::default();
+        let note = compose_note(&mut import, 1, " Review ", Some("tt core"), &["work".to_string(), " ".to_string()]);
+        assert_eq!("Review @tt_core #work", note);
+        assert_eq!(1, import.lossy_entries().count());
+        assert_eq!(0, import.skipped().count());
+
+        let note = compose_note(&mut import, 2, "", None, &[]);
+        assert_eq!("", note);
+        assert_eq!(1, import.issues.len());
+
+        report_seconds(&mut import, 3, Some(Duration::minutes(5)));
+        report_seconds(&mut import, 3, None);
+        assert_eq!(1, import.issues.len());
+        report_seconds(&mut import, 3, Some(Duration::seconds(305)));
+        assert_eq!("#3 lossy: 5 s of activity beyond whole minutes dropped", import.issues[1].to_string());
+    }
+}
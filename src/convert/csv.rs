@@ -0,0 +1,314 @@
+use std::io::{Read, Write};
+use chrono::{DateTime, Local, Duration, NaiveDate, NaiveTime, NaiveDateTime, TimeZone};
+use crate::record::Record;
+use crate::journal::JournalResult;
+use crate::convert::{Import, note_word};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    Minutes,
+    Hours,
+}
+
+impl Unit {
+    fn format(self, duration: Duration) -> String {
+        match self {
+            Unit::Minutes => duration.num_minutes().to_string(),
+            Unit::Hours => format!("{:.2}", duration.num_minutes() as f64 / 60.0),
+        }
+    }
+
+    fn parse(self, source: &str) -> Option<Duration> {
+        match self {
+            Unit::Minutes => source.parse::<i64>().ok().map(Duration::minutes),
+            Unit::Hours => source.parse::<f64>().ok()
+                .filter(|hours| hours.is_finite())
+                .map(|hours| Duration::minutes((hours * 60.0).round() as i64)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    StartDate,
+    StartTime,
+    End,
+    Activity(Unit),
+    Rest(Unit),
+    Note,
+    Tags,
+}
+
+impl Column {
+    pub fn header(self) -> &'static str {
+        match self {
+            Column::StartDate => "Start date",
+            Column::StartTime => "Start time",
+            Column::End => "End",
+            Column::Activity(Unit::Minutes) => "Activity (min)",
+            Column::Activity(Unit::Hours) => "Activity (h)",
+            Column::Rest(Unit::Minutes) => "Rest (min)",
+            Column::Rest(Unit::Hours) => "Rest (h)",
+            Column::Note => "Note",
+            Column::Tags => "Tags",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvFormat {
+    columns: Vec<Column>,
+    header: bool,
+    delimiter: u8,
+}
+
+impl Default for CsvFormat {
+    fn default() -> Self {
+        CsvFormat::new(vec![
+            Column::StartDate,
+            Column::StartTime,
+            Column::End,
+            Column::Activity(Unit::Minutes),
+            Column::Rest(Unit::Minutes),
+            Column::Note,
+            Column::Tags,
+        ])
+    }
+}
+
+impl CsvFormat {
+    pub const DATE_FORMAT: &'static str = "%Y-%m-%d";
+    pub const TIME_FORMAT: &'static str = "%H:%M:%S";
+
+    pub fn new(columns: Vec<Column>) -> Self {
+        CsvFormat {
+            columns,
+            header: true,
+            delimiter: b',',
+        }
+    }
+
+    pub fn with_header(mut self, header: bool) -> Self {
+        self.header = header;
+        self
+    }
+
+    pub fn with_delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    pub fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    pub fn export<W, I>(&self, writer: W, records: I) -> JournalResult<usize>
+        where W: Write,
+              I: IntoIterator<Item = Record>,
+    {
+        let mut writer = ::csv::WriterBuilder::new()
+            .delimiter(self.delimiter)
+            .from_writer(writer);
+        if self.header {
+            writer.write_record(self.columns.iter().map(|column| column.header()))?;
+        }
+
+        let mut count = 0;
+        for record in records {
+            writer.write_record(self.columns.iter().map(|column| self.format_cell(*column, &record)))?;
+            count += 1;
+        }
+        writer.flush()?;
+        Ok(count)
+    }
+
+    pub fn import<R: Read>(&self, reader: R) -> JournalResult<Import> {
+        let mut reader = ::csv::ReaderBuilder::new()
+            .delimiter(self.delimiter)
+            .has_headers(self.header)
+            .flexible(true)
+            .from_reader(reader);
+
+        let mut import = Import::default();
+        for row in reader.records() {
+            match row {
+                Ok(row) => {
+                    let line = row.position().map(|position| position.line() as usize).unwrap_or_default();
+                    match self.parse_row(&row) {
+                        Ok(record) => import.records.push(record),
+                        Err(message) => import.skip(line, message),
+                    }
+                },
+                Err(err) => {
+                    let line = err.position().map(|position| position.line() as usize).unwrap_or_default();
+                    import.skip(line, err.to_string());
+                },
+            }
+        }
+        Ok(import)
+    }
+
+    fn format_cell(&self, column: Column, record: &Record) -> String {
+        let format_datetime = |datetime: Option<DateTime<Local>>, format: &str| datetime
+            .map(|dt| dt.format(format).to_string())
+            .unwrap_or_default();
+
+        match column {
+            Column::StartDate => format_datetime(record.start, CsvFormat::DATE_FORMAT),
+            Column::StartTime => format_datetime(record.start, CsvFormat::TIME_FORMAT),
+            Column::End => format_datetime(record.end(), Record::START_DATETIME_FORMAT),
+            Column::Activity(unit) => record.activity.map(|activity| unit.format(activity)).unwrap_or_default(),
+            Column::Rest(unit) => record.rest.map(|rest| unit.format(rest)).unwrap_or_default(),
+            Column::Note => record.note.clone(),
+            Column::Tags => record.tags().join(" "),
+        }
+    }
+
+    fn parse_row(&self, row: &::csv::StringRecord) -> Result<Record, String> {
+        if row.len() != self.columns.len() {
+            return Err(format!("expected {} columns, found {}", self.columns.len(), row.len()));
+        }
+
+        let mut record = Record::default();
+        let mut date = None;
+        let mut time = None;
+        let mut end = None;
+        let mut tags = Vec::new();
+
+        for (column, cell) in self.columns.iter().zip(row.iter()) {
+            let cell = cell.trim();
+            if cell.is_empty() {
+                continue;
+            }
+            let invalid = || format!("invalid {} `{}`", column.header().to_lowercase(), cell);
+            match column {
+                Column::StartDate => date = Some(NaiveDate::parse_from_str(cell, CsvFormat::DATE_FORMAT)
+                    .map_err(|_| invalid())?),
+                Column::StartTime => time = Some(NaiveTime::parse_from_str(cell, CsvFormat::TIME_FORMAT)
+                    .or_else(|_| NaiveTime::parse_from_str(cell, "%H:%M"))
+                    .map_err(|_| invalid())?),
+                Column::End => end = Some(NaiveDateTime::parse_from_str(cell, Record::START_DATETIME_FORMAT)
+                    .ok()
+                    .and_then(|naive| Local.from_local_datetime(&naive).earliest())
+                    .ok_or_else(invalid)?),
+                Column::Activity(unit) => record.activity = Some(unit.parse(cell).ok_or_else(invalid)?),
+                Column::Rest(unit) => record.rest = Some(unit.parse(cell).ok_or_else(invalid)?),
+                Column::Note => record.note = cell.to_string(),
+                Column::Tags => tags.extend(cell.split_whitespace().map(str::to_string)),
+            }
+        }
+
+        record.start = match (date, time) {
+            (Some(date), Some(time)) => Some(
+                Local.from_local_datetime(&date.and_time(time)).earliest()
+                    .ok_or_else(|| "nonexistent local start time".to_string())?
+            ),
+            (Some(date), None) => date.and_hms_opt(0, 0, 0)
+                .and_then(|naive| Local.from_local_datetime(&naive).earliest()),
+            (None, Some(_)) => return Err("start time without start date".to_string()),
+            (None, None) => None,
+        };
+
+        if let (Some(start), Some(end), None) = (record.start, end, record.activity) {
+            record.activity = Some(end - start - record.rest.unwrap_or_else(|| Duration::minutes(0)));
+        }
+
+        for tag in tags {
+            let tag = tag.trim_start_matches(Record::TAG_PREFIX);
+            if !record.has_tag(tag) {
+                let (word, _) = note_word(Record::TAG_PREFIX, tag);
+                if !record.note.is_empty() {
+                    record.note.push(' ');
+                }
+                record.note += &word;
+            }
+        }
+        Ok(record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convert::IssueKind;
+
+    fn record(start: &str, activity: Option<i64>, rest: Option<i64>, note: &str) -> Record {
+        Record {
+            start: NaiveDateTime::parse_from_str(start, Record::START_DATETIME_FORMAT).ok()
+                .and_then(|naive| Local.from_local_datetime(&naive).earliest()),
+            activity: activity.map(Duration::minutes),
+            rest: rest.map(Duration::minutes),
+            note: note.to_string(),
+        }
+    }
+
+    #[test]
+    fn export_default() {
+        let records = vec![
+            record("2018-08-16 13:52:43", Some(42), Some(1), "Note 1 #work"),
+            record("2018-08-16 15:40:25", Some(42), Some(-5), "Note, 2"),
+            record("", None, None, ""),
+        ];
+        let mut output = Vec::new();
+        assert_eq!(3, CsvFormat::default().export(&mut output, records).unwrap());
+        assert_eq!("Start date,Start time,End,Activity (min),Rest (min),Note,Tags
+2018-08-16,13:52:43,2018-08-16 14:35:43,42,1,Note 1 #work,work
+2018-08-16,15:40:25,2018-08-16 16:17:25,42,-5,\"Note, 2\",
+,,,,,,
+", String::from_utf8(output).unwrap());
+    }
+
+    #[test]
+    fn export_hours_without_header() {
+        let format = CsvFormat::new(vec![Column::StartDate, Column::Activity(Unit::Hours), Column::Rest(Unit::Hours)])
+            .with_header(false)
+            .with_delimiter(b';');
+        let mut output = Vec::new();
+        format.export(&mut output, vec![record("2018-08-16 13:52:43", Some(85), Some(-5), "")]).unwrap();
+        assert_eq!("2018-08-16;1.42;-0.08\n", String::from_utf8(output).unwrap());
+    }
+
+    #[test]
+    fn round_trip() {
+        let records = vec![
+            record("2018-08-16 13:52:43", Some(42), Some(1), "Note 1 #work"),
+            record("2018-08-16 15:40:25", Some(42), Some(-5), "Note, 2"),
+            record("", None, None, ""),
+        ];
+        let mut output = Vec::new();
+        CsvFormat::default().export(&mut output, records.iter().cloned()).unwrap();
+        let import = CsvFormat::default().import(&output[..]).unwrap();
+        assert!(import.issues.is_empty());
+        assert_eq!(records, import.records);
+    }
+
+    #[test]
+    fn import_invalid_rows() {
+        let source = "Start date,Start time,End,Note,Tags
+2018-08-16,13:52,2018-08-16 14:35:00,Note 1,work review
+16.08.2018,13:52,,Note 2,
+2018-08-16,,,Note 3,
+2018-08-16,10:00
+,,,Note 4,#work
+";
+        let format = CsvFormat::new(vec![Column::StartDate, Column::StartTime, Column::End, Column::Note, Column::Tags]);
+        let import = format.import(source.as_bytes()).unwrap();
+        assert_eq!(vec![
+            record("2018-08-16 13:52:00", Some(43), None, "Note 1 #work #review"),
+            record("2018-08-16 00:00:00", None, None, "Note 3"),
+            record("", None, None, "Note 4 #work"),
+        ], import.records);
+
+        assert_eq!(2, import.issues.len());
+        assert_eq!(3, import.issues[0].position);
+        assert_eq!(IssueKind::Skipped, import.issues[0].kind);
+        assert_eq!("invalid start date `16.08.2018`", import.issues[0].message);
+        assert_eq!(5, import.issues[1].position);
+        assert_eq!("expected 5 columns, found 2", import.issues[1].message);
+
+        let source = "Start date,Start time,End,Note,Tags\n2018-08-16,13:52,,\"Note\n1\",\n16.08.2018,13:52,,Note 2,\n";
+        let import = format.import(source.as_bytes()).unwrap();
+        assert_eq!(1, import.records.len());
+        assert_eq!(4, import.issues[0].position);
+    }
+}
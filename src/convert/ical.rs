@@ -0,0 +1,346 @@
+use std::io::{Read, Write};
+use chrono::{DateTime, Local, Utc, Duration, NaiveDateTime, TimeZone};
+use crate::record::Record;
+use crate::journal::JournalResult;
+use crate::convert::Import;
+
+const UTC_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+const LOCAL_FORMAT: &str = "%Y%m%dT%H%M%S";
+const MAX_LINE_LEN: usize = 75;
+
+pub fn uid(record: &Record) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    let start = record.start.map(|start| start.timestamp()).unwrap_or_default();
+    for byte in start.to_string().bytes().chain(Some(b'|')).chain(record.note.bytes()) {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("{:016x}@tt-core", hash)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+fn unescape(text: &str) -> String {
+    let mut result = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n') | Some('N') => result.push('\n'),
+                Some(c) => result.push(c),
+                None => result.push('\\'),
+            }
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut len = 0;
+    for c in line.chars() {
+        if len + c.len_utf8() > MAX_LINE_LEN {
+            folded += "\r\n ";
+            len = 1;
+        }
+        folded.push(c);
+        len += c.len_utf8();
+    }
+    folded + "\r\n"
+}
+
+/// Sequence number of an event exported at `stamp`: the minutes since the
+/// epoch, so a re-export supersedes the events exported before.
+pub fn sequence(stamp: DateTime<Utc>) -> i64 {
+    stamp.timestamp().div_euclid(60)
+}
+
+pub fn format_event(record: &Record, stamp: DateTime<Utc>) -> Option<String> {
+    let start = record.start?.with_timezone(&Utc);
+    let end = record.end()?.with_timezone(&Utc);
+    let lines = [
+        "BEGIN:VEVENT".to_string(),
+        format!("UID:{}", uid(record)),
+        format!("DTSTAMP:{}", stamp.format(UTC_FORMAT)),
+        format!("SEQUENCE:{}", sequence(stamp)),
+        format!("DTSTART:{}", start.format(UTC_FORMAT)),
+        format!("DTEND:{}", end.format(UTC_FORMAT)),
+        format!("SUMMARY:{}", escape(&record.note)),
+        "END:VEVENT".to_string(),
+    ];
+    Some(lines.iter().map(|line| fold(line)).collect())
+}
+
+pub fn export<W, I>(writer: W, records: I) -> JournalResult<usize>
+    where W: Write,
+          I: IntoIterator<Item = Record>,
+{
+    export_at(writer, records, Utc::now())
+}
+
+pub fn export_at<W, I>(mut writer: W, records: I, stamp: DateTime<Utc>) -> JournalResult<usize>
+    where W: Write,
+          I: IntoIterator<Item = Record>,
+{
+    writer.write_all(b"BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//tt-core//EN\r\n")?;
+    let mut count = 0;
+    for record in records {
+        if let Some(event) = format_event(&record, stamp) {
+            writer.write_all(event.as_bytes())?;
+            count += 1;
+        }
+    }
+    writer.write_all(b"END:VCALENDAR\r\n")?;
+    writer.flush()?;
+    Ok(count)
+}
+
+fn unfold(source: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in source.lines() {
+        let line = line.trim_end_matches('\r');
+        match (line.chars().next(), lines.last_mut()) {
+            (Some(' '), Some(last)) | (Some('\t'), Some(last)) => last.push_str(&line[1..]),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+fn parse_datetime(import: &mut Import, position: usize, params: &str, value: &str) -> Result<DateTime<Local>, String> {
+    if params.split(';').any(|param| param.eq_ignore_ascii_case("VALUE=DATE")) {
+        return Err("all-day event".to_string());
+    }
+    if let Ok(naive) = NaiveDateTime::parse_from_str(value, UTC_FORMAT) {
+        return Ok(Utc.from_utc_datetime(&naive).with_timezone(&Local));
+    }
+    let naive = NaiveDateTime::parse_from_str(value, LOCAL_FORMAT)
+        .map_err(|_| format!("invalid datetime `{}`", value))?;
+    if let Some(tzid) = params.split(';').find(|param| param.to_uppercase().starts_with("TZID=")) {
+        import.lossy(position, format!("time zone `{}` treated as local time", &tzid[5..]));
+    }
+    Local.from_local_datetime(&naive).earliest().ok_or_else(|| format!("nonexistent local time `{}`", value))
+}
+
+fn parse_duration(value: &str) -> Option<Duration> {
+    let (negative, value) = match value.chars().next()? {
+        '-' => (true, &value[1..]),
+        '+' => (false, &value[1..]),
+        _ => (false, value),
+    };
+    let mut duration = Duration::zero();
+    let mut number = String::new();
+    for c in value.strip_prefix('P')?.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'T' => continue,
+            unit => {
+                let amount = number.parse::<i64>().ok()?;
+                number.clear();
+                duration += match unit {
+                    'W' => Duration::weeks(amount),
+                    'D' => Duration::days(amount),
+                    'H' => Duration::hours(amount),
+                    'M' => Duration::minutes(amount),
+                    'S' => Duration::seconds(amount),
+                    _ => return None,
+                };
+            },
+        }
+    }
+    if !number.is_empty() {
+        return None;
+    }
+    Some(if negative { -duration } else { duration })
+}
+
+pub fn import<R: Read>(mut reader: R) -> JournalResult<Import> {
+    let mut source = String::new();
+    reader.read_to_string(&mut source)?;
+
+    let mut import = Import::default();
+    let mut event: Option<Vec<(String, String, String)>> = None;
+    let mut position = 0;
+    for line in unfold(&source) {
+        let (name, value) = match line.find(':') {
+            Some(idx) => (&line[..idx], &line[idx + 1..]),
+            None => continue,
+        };
+        let (name, params) = match name.find(';') {
+            Some(idx) => (&name[..idx], &name[idx + 1..]),
+            None => (name, ""),
+        };
+        let name = name.to_uppercase();
+
+        match (name.as_str(), value) {
+            ("BEGIN", "VEVENT") => {
+                position += 1;
+                event = Some(Vec::new());
+            },
+            ("END", "VEVENT") => if let Some(properties) = event.take() {
+                match parse_event(&mut import, position, &properties) {
+                    Ok(record) => import.records.push(record),
+                    Err(message) => import.skip(position, message),
+                }
+            },
+            _ => if let Some(properties) = event.as_mut() {
+                properties.push((name.clone(), params.to_string(), value.to_string()));
+            },
+        }
+    }
+    Ok(import)
+}
+
+fn parse_event(import: &mut Import, position: usize, properties: &[(String, String, String)]) -> Result<Record, String> {
+    let property = |name: &str| properties.iter().find(|(n, _, _)| n == name);
+
+    let start = match property("DTSTART") {
+        Some((_, params, value)) => parse_datetime(import, position, params, value)?,
+        None => return Err("missing DTSTART".to_string()),
+    };
+    let activity = if let Some((_, params, value)) = property("DTEND") {
+        Some(parse_datetime(import, position, params, value)? - start)
+    } else if let Some((_, _, value)) = property("DURATION") {
+        Some(parse_duration(value).ok_or_else(|| format!("invalid duration `{}`", value))?)
+    } else {
+        None
+    };
+    if property("RRULE").is_some() {
+        import.lossy(position, "recurrence rule ignored, only the first occurrence imported");
+    }
+
+    let summary = property("SUMMARY").map(|(_, _, value)| unescape(value)).unwrap_or_default();
+    if summary.contains(&['\r', '\n'][..]) {
+        import.lossy(position, "line breaks in summary stored escaped as `\\n`");
+    }
+    Ok(Record {
+        start: Some(start),
+        activity,
+        rest: None,
+        note: summary.replace("\r\n", "\n").replace('\r', "\n").replace('\n', "\\n"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convert::IssueKind;
+
+    fn utc(source: &str) -> Option<DateTime<Local>> {
+        NaiveDateTime::parse_from_str(source, UTC_FORMAT).ok()
+            .map(|naive| Utc.from_utc_datetime(&naive).with_timezone(&Local))
+    }
+
+    #[test]
+    fn stable_uid() {
+        let record = Record {
+            start: utc("20180816T135243Z"),
+            activity: Some(Duration::minutes(42)),
+            rest: None,
+            note: "Note 1".to_string(),
+        };
+        let updated = Record { activity: Some(Duration::minutes(50)), ..record.clone() };
+        assert_eq!(uid(&record), uid(&updated));
+        assert_ne!(uid(&record), uid(&Record { note: "Note 2".to_string(), ..updated }));
+    }
+
+    #[test]
+    fn export_records() {
+        let records = vec![
+            Record {
+                start: utc("20180816T135243Z"),
+                activity: Some(Duration::minutes(42)),
+                rest: Some(Duration::minutes(-5)),
+                note: "Review, fix; ship".to_string(),
+            },
+            Record::default(),
+        ];
+        let stamp = utc("20180820T090000Z").unwrap().with_timezone(&Utc);
+        let mut output = Vec::new();
+        assert_eq!(1, export_at(&mut output, records, stamp).unwrap());
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(output.contains("\r\nDTSTAMP:20180820T090000Z\r\nSEQUENCE:25579260\r\n"));
+        assert!(output.contains("DTSTART:20180816T135243Z\r\nDTEND:20180816T142943Z\r\nSUMMARY:Review\\, fix\\; ship\r\n"));
+        assert!(output.ends_with("END:VEVENT\r\nEND:VCALENDAR\r\n"));
+    }
+
+    #[test]
+    fn folding() {
+        let line = "SUMMARY:".to_string() + &"x".repeat(100);
+        let folded = fold(&line);
+        assert_eq!(vec![line], unfold(&folded));
+        assert!(folded.split("\r\n").all(|part| part.len() <= MAX_LINE_LEN));
+    }
+
+    #[test]
+    fn round_trip() {
+        let records = vec![
+            Record {
+                start: utc("20180816T135243Z"),
+                activity: Some(Duration::minutes(42)),
+                rest: None,
+                note: format!("Long note {} with \\ backslash, and; more", "word ".repeat(20).trim()),
+            },
+        ];
+        let mut output = Vec::new();
+        export(&mut output, records.iter().cloned()).unwrap();
+        let import = import(&output[..]).unwrap();
+        assert!(import.issues.is_empty());
+        assert_eq!(records, import.records);
+    }
+
+    #[test]
+    fn import_meetings() {
+        let source = "BEGIN:VCALENDAR
+BEGIN:VEVENT
+DTSTART;TZID=Europe/Berlin:20180816T100000
+DURATION:PT1H30M
+SUMMARY:Planning
+RRULE:FREQ=WEEKLY
+END:VEVENT
+BEGIN:VEVENT
+DTSTART;VALUE=DATE:20180817
+SUMMARY:Holiday
+END:VEVENT
+BEGIN:VEVENT
+DTSTART:20180818T100000Z
+SUMMARY:Call\\nwith  team
+END:VEVENT
+END:VCALENDAR
+";
+        let import = import(source.as_bytes()).unwrap();
+        assert_eq!(2, import.records.len());
+        assert_eq!(Some(Duration::minutes(90)), import.records[0].activity);
+        assert_eq!("Planning", import.records[0].note);
+        assert_eq!(utc("20180818T100000Z"), import.records[1].start);
+        assert_eq!(None, import.records[1].activity);
+        assert_eq!("Call\\nwith  team", import.records[1].note);
+
+        let issues = import.issues.iter()
+            .map(|issue| (issue.position, issue.kind))
+            .collect::<Vec<_>>();
+        assert_eq!(vec![
+            (1, IssueKind::Lossy),
+            (1, IssueKind::Lossy),
+            (2, IssueKind::Skipped),
+            (3, IssueKind::Lossy),
+        ], issues);
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(Some(Duration::minutes(90)), parse_duration("PT1H30M"));
+        assert_eq!(Some(Duration::days(1) + Duration::seconds(5)), parse_duration("P1DT5S"));
+        assert_eq!(Some(-Duration::weeks(2)), parse_duration("-P2W"));
+        assert_eq!(None, parse_duration("PT1X"));
+        assert_eq!(None, parse_duration("1H"));
+    }
+}
@@ -0,0 +1,129 @@
+use std::io::Write;
+use crate::record::{Record, RecordMatcher};
+use crate::journal::JournalResult;
+
+pub const DATETIME_FORMAT: &str = "%Y/%m/%d %H:%M:%S";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountMap {
+    rules: Vec<(RecordMatcher, String)>,
+    default_account: String,
+}
+
+impl Default for AccountMap {
+    fn default() -> Self {
+        AccountMap::new("unassigned")
+    }
+}
+
+impl AccountMap {
+    pub fn new<S: Into<String>>(default_account: S) -> Self {
+        AccountMap {
+            rules: Vec::new(),
+            default_account: default_account.into(),
+        }
+    }
+
+    pub fn tag<T: Into<String>, A: Into<String>>(mut self, tag: T, account: A) -> Self {
+        self.rules.push((RecordMatcher::Tag(tag.into()), account.into()));
+        self
+    }
+
+    pub fn project<P: Into<String>, A: Into<String>>(mut self, project: P, account: A) -> Self {
+        self.rules.push((RecordMatcher::Project(project.into()), account.into()));
+        self
+    }
+
+    pub fn account(&self, record: &Record) -> &str {
+        self.rules.iter()
+            .find(|(matcher, _)| matcher.matches(record))
+            .map(|(_, account)| account.as_str())
+            .unwrap_or(&self.default_account)
+    }
+}
+
+pub fn description(record: &Record) -> String {
+    record.note
+        .split_whitespace()
+        .filter(|word| word.len() < 2
+            || !(word.starts_with(Record::TAG_PREFIX) || word.starts_with(Record::PROJECT_PREFIX)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+pub fn format_entry(record: &Record, accounts: &AccountMap) -> Option<String> {
+    let start = record.start?;
+    record.activity?;
+    let end = record.end()?;
+
+    let description = description(record);
+    let clock_in = format!("i {} {}", start.format(DATETIME_FORMAT), accounts.account(record));
+    let clock_in = if description.is_empty() {
+        clock_in
+    } else {
+        format!("{}  {}", clock_in, description)
+    };
+    Some(format!("{}\no {}\n", clock_in, end.format(DATETIME_FORMAT)))
+}
+
+pub fn export<W, I>(mut writer: W, records: I, accounts: &AccountMap) -> JournalResult<usize>
+    where W: Write,
+          I: IntoIterator<Item = Record>,
+{
+    let mut count = 0;
+    for record in records {
+        if let Some(entry) = format_entry(&record, accounts) {
+            writer.write_all(entry.as_bytes())?;
+            count += 1;
+        }
+    }
+    writer.flush()?;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Local, Duration, NaiveDateTime, TimeZone};
+
+    fn record(start: &str, activity: Option<i64>, rest: Option<i64>, note: &str) -> Record {
+        Record {
+            start: NaiveDateTime::parse_from_str(start, Record::START_DATETIME_FORMAT).ok()
+                .and_then(|naive| Local.from_local_datetime(&naive).earliest()),
+            activity: activity.map(Duration::minutes),
+            rest: rest.map(Duration::minutes),
+            note: note.to_string(),
+        }
+    }
+
+    #[test]
+    fn account_mapping() {
+        let accounts = AccountMap::new("work:misc")
+            .project("tt-core", "work:tt-core")
+            .tag("meeting", "work:meetings");
+        assert_eq!("work:tt-core", accounts.account(&record("", None, None, "Review @tt-core #meeting")));
+        assert_eq!("work:meetings", accounts.account(&record("", None, None, "Standup #meeting")));
+        assert_eq!("work:misc", accounts.account(&record("", None, None, "Standup @other")));
+    }
+
+    #[test]
+    fn export_records() {
+        let accounts = AccountMap::default().tag("work", "work");
+        let records = vec![
+            record("2018-08-16 13:52:43", Some(42), Some(1), "Note 1 #work"),
+            record("2018-08-16 15:40:25", Some(42), Some(-5), "#work"),
+            record("2018-08-16 18:12:01", None, None, "Running"),
+            record("", Some(85), None, "Without start"),
+            record("2018-08-16 23:30:00", Some(60), None, "Late"),
+        ];
+        let mut output = Vec::new();
+        assert_eq!(3, export(&mut output, records, &accounts).unwrap());
+        assert_eq!("i 2018/08/16 13:52:43 work  Note 1
+o 2018/08/16 14:35:43
+i 2018/08/16 15:40:25 work
+o 2018/08/16 16:17:25
+i 2018/08/16 23:30:00 unassigned  Late
+o 2018/08/17 00:30:00
+", String::from_utf8(output).unwrap());
+    }
+}
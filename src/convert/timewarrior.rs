@@ -0,0 +1,214 @@
+use std::fs;
+use std::path::Path;
+use std::io::{BufRead, BufReader};
+use chrono::{DateTime, Local, Utc, NaiveDateTime, TimeZone};
+use crate::record::Record;
+use crate::journal::JournalResult;
+use crate::convert::{Import, compose_note, report_seconds};
+
+const DATETIME_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Word(String),
+    Quoted(String),
+}
+
+impl Token {
+    fn into_string(self) -> String {
+        match self {
+            Token::Word(s) | Token::Quoted(s) => s,
+        }
+    }
+}
+
+fn tokenize(line: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = line.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' {
+            chars.next();
+            let mut value = String::new();
+            loop {
+                match chars.next() {
+                    Some('\\') => value.extend(chars.next()),
+                    Some('"') => break,
+                    Some(c) => value.push(c),
+                    None => return Err("unterminated quote".to_string()),
+                }
+            }
+            tokens.push(Token::Quoted(value));
+        } else {
+            let mut value = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                value.push(c);
+                chars.next();
+            }
+            tokens.push(Token::Word(value));
+        }
+    }
+    Ok(tokens)
+}
+
+fn parse_datetime(source: &str) -> Result<DateTime<Local>, String> {
+    NaiveDateTime::parse_from_str(source, DATETIME_FORMAT)
+        .map(|naive| Utc.from_utc_datetime(&naive).with_timezone(&Local))
+        .map_err(|_| format!("invalid datetime `{}`", source))
+}
+
+fn parse_line(import: &mut Import, position: usize, line: &str) -> Result<Record, String> {
+    let mut tokens = tokenize(line)?.into_iter();
+    if tokens.next() != Some(Token::Word("inc".to_string())) {
+        return Err("not an interval".to_string());
+    }
+
+    let start = match tokens.next() {
+        Some(Token::Word(start)) => parse_datetime(&start)?,
+        _ => return Err("missing interval start".to_string()),
+    };
+
+    let mut end = None;
+    let mut sections = vec![Vec::new()];
+    for token in tokens {
+        match token {
+            Token::Word(ref word) if word == "-" && end.is_none() && sections.len() == 1 => end = Some(None),
+            Token::Word(ref word) if word == "#" => sections.push(Vec::new()),
+            Token::Word(word) if end == Some(None) => end = Some(Some(parse_datetime(&word)?)),
+            token => if let Some(section) = sections.last_mut() {
+                section.push(token.into_string());
+            },
+        }
+    }
+
+    let mut sections = sections.into_iter().skip(1);
+    let tags = sections.next().unwrap_or_default();
+    let annotation = sections.map(|section| section.join(" ")).collect::<Vec<_>>().join(" ");
+
+    let activity = match end {
+        Some(Some(end)) => Some(end - start),
+        Some(None) => return Err("missing interval end".to_string()),
+        None => {
+            import.lossy(position, "open interval imported without activity");
+            None
+        },
+    };
+    report_seconds(import, position, activity);
+
+    Ok(Record {
+        start: Some(start),
+        activity,
+        rest: None,
+        note: compose_note(import, position, &annotation, None, &tags),
+    })
+}
+
+pub fn import<R: BufRead>(reader: R) -> JournalResult<Import> {
+    let mut import = Import::default();
+    import_into(&mut import, reader, 0)?;
+    Ok(import)
+}
+
+pub fn import_dir<P: AsRef<Path>>(dir: P) -> JournalResult<Import> {
+    let mut files = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().map(|ext| ext == "data").unwrap_or(false))
+        .collect::<Vec<_>>();
+    files.sort();
+
+    let mut import = Import::default();
+    let mut position = 0;
+    for file in files {
+        position = import_into(&mut import, BufReader::new(fs::File::open(file)?), position)?;
+    }
+    Ok(import)
+}
+
+fn import_into<R: BufRead>(import: &mut Import, reader: R, mut position: usize) -> JournalResult<usize> {
+    for line in reader.lines() {
+        let line = line?;
+        position += 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match parse_line(import, position, &line) {
+            Ok(record) => import.records.push(record),
+            Err(message) => import.skip(position, message),
+        }
+    }
+    Ok(position)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use crate::convert::IssueKind;
+
+    fn utc(source: &str) -> Option<DateTime<Local>> {
+        parse_datetime(source).ok()
+    }
+
+    #[test]
+    fn tokens() {
+        assert_eq!(vec![
+            Token::Word("inc".to_string()),
+            Token::Word("#".to_string()),
+            Token::Quoted("tag \"two\"".to_string()),
+        ], tokenize(r#"inc  # "tag \"two\"""#).unwrap());
+        assert!(tokenize(r#"inc "tag"#).is_err());
+    }
+
+    #[test]
+    fn import_data() {
+        let data = r#"inc 20180726T200341Z - 20180726T202841Z # work "code review" # "Some annotation"
+inc 20180726T203000Z - 20180726T204500Z
+inc 20180726T210000Z # work
+inc 2018-07-26 - 20180726T204500Z
+
+exc monday 18:00:00-09:00:00
+inc 20180726T220000Z -
+"#;
+        let import = import(data.as_bytes()).unwrap();
+        assert_eq!(vec![
+            Record {
+                start: utc("20180726T200341Z"),
+                activity: Some(Duration::minutes(25)),
+                rest: None,
+                note: "Some annotation #work #code_review".to_string(),
+            },
+            Record {
+                start: utc("20180726T203000Z"),
+                activity: Some(Duration::minutes(15)),
+                rest: None,
+                note: String::new(),
+            },
+            Record {
+                start: utc("20180726T210000Z"),
+                activity: None,
+                rest: None,
+                note: "#work".to_string(),
+            },
+        ], import.records);
+
+        let issues = import.issues.iter()
+            .map(|issue| (issue.position, issue.kind))
+            .collect::<Vec<_>>();
+        assert_eq!(vec![
+            (1, IssueKind::Lossy),
+            (3, IssueKind::Lossy),
+            (4, IssueKind::Skipped),
+            (6, IssueKind::Skipped),
+            (7, IssueKind::Skipped),
+        ], issues);
+        assert_eq!("invalid datetime `2018-07-26`", import.issues[2].message);
+
+        let seconds = super::import("inc 20180726T200341Z - 20180726T202811Z\n".as_bytes()).unwrap();
+        assert_eq!(Some(Duration::seconds(1470)), seconds.records[0].activity);
+        assert_eq!(vec![(1, IssueKind::Lossy)], seconds.issues.iter().map(|issue| (issue.position, issue.kind)).collect::<Vec<_>>());
+    }
+}
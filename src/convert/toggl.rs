@@ -0,0 +1,227 @@
+use std::io::Read;
+use std::collections::HashMap;
+use chrono::{Local, Duration, NaiveDate, NaiveTime, TimeZone};
+use crate::record::Record;
+use crate::journal::JournalResult;
+use crate::convert::{Import, compose_note, report_seconds};
+
+fn parse_duration(source: &str) -> Option<Duration> {
+    let parts = source.split(':')
+        .map(|part| part.trim().parse::<i64>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    match parts[..] {
+        [hours, minutes, seconds] => Some(Duration::seconds(hours * 3600 + minutes * 60 + seconds)),
+        [hours, minutes] => Some(Duration::minutes(hours * 60 + minutes)),
+        _ => None,
+    }
+}
+
+fn split_tags(source: &str) -> Vec<String> {
+    source.split(',')
+        .map(|tag| tag.trim().to_string())
+        .filter(|tag| !tag.is_empty())
+        .collect()
+}
+
+fn report_dropped(import: &mut Import, position: usize, field: &str, value: &str) {
+    let value = value.trim();
+    if !value.is_empty() {
+        import.lossy(position, format!("{} `{}` dropped", field, value));
+    }
+}
+
+pub fn import_csv<R: Read>(reader: R) -> JournalResult<Import> {
+    let mut reader = ::csv::ReaderBuilder::new().flexible(true).from_reader(reader);
+    let headers = reader.headers()?
+        .iter()
+        .enumerate()
+        .map(|(idx, header)| (header.trim().to_lowercase(), idx))
+        .collect::<HashMap<_, _>>();
+
+    let mut import = Import::default();
+    for row in reader.records() {
+        let row = match row {
+            Ok(row) => row,
+            Err(err) => {
+                import.skip(err.position().map(|position| position.line() as usize).unwrap_or_default(), err.to_string());
+                continue;
+            },
+        };
+        let position = row.position().map(|position| position.line() as usize).unwrap_or_default();
+        let field = |name: &str| headers.get(name)
+            .and_then(|&idx| row.get(idx))
+            .unwrap_or("")
+            .trim();
+
+        let date = NaiveDate::parse_from_str(field("start date"), "%Y-%m-%d");
+        let time = NaiveTime::parse_from_str(field("start time"), "%H:%M:%S");
+        let start = match (date, time) {
+            (Ok(date), Ok(time)) => Local.from_local_datetime(&date.and_time(time)).earliest(),
+            _ => None,
+        };
+        let start = match start {
+            Some(start) => start,
+            None => {
+                import.skip(position, format!("invalid start `{} {}`", field("start date"), field("start time")));
+                continue;
+            },
+        };
+
+        let end = NaiveDate::parse_from_str(field("end date"), "%Y-%m-%d").ok()
+            .and_then(|date| NaiveTime::parse_from_str(field("end time"), "%H:%M:%S").ok()
+                .map(|time| date.and_time(time)))
+            .and_then(|naive| Local.from_local_datetime(&naive).earliest());
+        let activity = parse_duration(field("duration")).or_else(|| end.map(|end| end - start));
+        if activity.is_none() {
+            import.lossy(position, "entry imported without activity");
+        }
+        report_seconds(&mut import, position, activity);
+
+        report_dropped(&mut import, position, "client", field("client"));
+        report_dropped(&mut import, position, "task", field("task"));
+
+        let project = field("project");
+        let tags = split_tags(field("tags"));
+        let note = compose_note(&mut import, position, field("description"), Some(project), &tags);
+        import.records.push(Record {
+            start: Some(start),
+            activity,
+            rest: None,
+            note,
+        });
+    }
+    Ok(import)
+}
+
+#[cfg(feature = "json")]
+pub fn import_json<R: Read>(reader: R) -> JournalResult<Import> {
+    use chrono::DateTime;
+    use serde_json::Value;
+
+    let value = serde_json::from_reader::<_, Value>(reader)?;
+    let entries = match value {
+        Value::Array(entries) => entries,
+        Value::Object(mut object) => match object.remove("data") {
+            Some(Value::Array(entries)) => entries,
+            _ => Vec::new(),
+        },
+        _ => Vec::new(),
+    };
+
+    let mut import = Import::default();
+    for (idx, entry) in entries.iter().enumerate() {
+        let position = idx + 1;
+        let text = |name: &str| entry.get(name).and_then(Value::as_str).unwrap_or("");
+        let datetime = |name: &str| DateTime::parse_from_rfc3339(text(name)).ok()
+            .map(|dt| dt.with_timezone(&Local));
+
+        let start = match datetime("start") {
+            Some(start) => start,
+            None => {
+                import.skip(position, format!("invalid start `{}`", text("start")));
+                continue;
+            },
+        };
+        let end = datetime("stop").or_else(|| datetime("end"));
+        let activity = entry.get("dur").and_then(Value::as_i64).map(Duration::milliseconds)
+            .or_else(|| entry.get("duration").and_then(Value::as_i64)
+                .filter(|&seconds| seconds >= 0)
+                .map(Duration::seconds))
+            .or_else(|| end.map(|end| end - start));
+        if activity.is_none() {
+            import.lossy(position, "running entry imported without activity");
+        }
+        report_seconds(&mut import, position, activity);
+
+        report_dropped(&mut import, position, "client", text("client"));
+        if let Some(project_id) = entry.get("project_id").and_then(Value::as_i64) {
+            if text("project").is_empty() {
+                import.lossy(position, format!("project id `{}` dropped", project_id));
+            }
+        }
+
+        let tags = entry.get("tags")
+            .and_then(Value::as_array)
+            .map(|tags| tags.iter().filter_map(Value::as_str).map(str::to_string).collect::<Vec<_>>())
+            .unwrap_or_default();
+        let note = compose_note(&mut import, position, text("description"), Some(text("project")), &tags);
+        import.records.push(Record {
+            start: Some(start),
+            activity,
+            rest: None,
+            note,
+        });
+    }
+    Ok(import)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDateTime;
+    use crate::convert::IssueKind;
+
+    fn local(source: &str) -> Option<chrono::DateTime<Local>> {
+        NaiveDateTime::parse_from_str(source, Record::START_DATETIME_FORMAT).ok()
+            .and_then(|naive| Local.from_local_datetime(&naive).earliest())
+    }
+
+    #[test]
+    fn import_csv_export() {
+        let source = "User,Email,Client,Project,Task,Description,Billable,Start date,Start time,End date,End time,Duration,Tags,Amount ()
+XX,xx@example.com,,tt core,,Review,No,2018-07-26,23:03:41,2018-07-26,23:28:41,00:25:00,\"work, review\",
+XX,xx@example.com,ACME,,Design,Meeting,Yes,2018-07-27,10:00:00,2018-07-27,11:30:00,,,
+XX,xx@example.com,,,,Broken,No,27.07.2018,10:00:00,,,,,
+";
+        let import = import_csv(source.as_bytes()).unwrap();
+        assert_eq!(vec![
+            Record {
+                start: local("2018-07-26 23:03:41"),
+                activity: Some(Duration::minutes(25)),
+                rest: None,
+                note: "Review @tt_core #work #review".to_string(),
+            },
+            Record {
+                start: local("2018-07-27 10:00:00"),
+                activity: Some(Duration::minutes(90)),
+                rest: None,
+                note: "Meeting".to_string(),
+            },
+        ], import.records);
+
+        let issues = import.issues.iter()
+            .map(|issue| (issue.position, issue.kind, issue.message.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(vec![
+            (2, IssueKind::Lossy, "project `tt core` stored as `@tt_core`"),
+            (3, IssueKind::Lossy, "client `ACME` dropped"),
+            (3, IssueKind::Lossy, "task `Design` dropped"),
+            (4, IssueKind::Skipped, "invalid start `27.07.2018 10:00:00`"),
+        ], issues);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn import_json_export() {
+        let source = r#"{"data": [
+            {"description": "Review", "start": "2018-07-26T23:03:41+00:00", "end": "2018-07-26T23:28:41+00:00",
+             "dur": 1500000, "project": "tt-core", "tags": ["work"]},
+            {"description": "Running", "start": "2018-07-27T10:00:00Z", "duration": -1532685600, "project_id": 42},
+            {"description": "Broken", "start": "yesterday"}
+        ]}"#;
+        let import = import_json(source.as_bytes()).unwrap();
+        assert_eq!(2, import.records.len());
+        assert_eq!(Some(Duration::minutes(25)), import.records[0].activity);
+        assert_eq!("Review @tt-core #work", import.records[0].note);
+        assert_eq!(Some(1_532_646_221), import.records[0].start.map(|start| start.timestamp()));
+        assert_eq!(None, import.records[1].activity);
+
+        let issues = import.issues.iter()
+            .map(|issue| (issue.position, issue.kind))
+            .collect::<Vec<_>>();
+        assert_eq!(vec![(2, IssueKind::Lossy), (2, IssueKind::Lossy), (3, IssueKind::Skipped)], issues);
+
+        let import = import_json(r#"[{"start": "2018-07-27T10:00:00Z", "stop": "2018-07-27T10:30:00Z", "duration": 1800}]"#.as_bytes()).unwrap();
+        assert_eq!(Some(Duration::minutes(30)), import.records[0].activity);
+    }
+}
@@ -0,0 +1,78 @@
+use std::io::Read;
+use chrono::{Local, TimeZone};
+use serde_json::Value;
+use crate::record::Record;
+use crate::journal::JournalResult;
+use crate::convert::{Import, compose_note, report_seconds};
+
+pub fn import_frames<R: Read>(reader: R) -> JournalResult<Import> {
+    let frames = serde_json::from_reader::<_, Vec<Value>>(reader)?;
+
+    let mut import = Import::default();
+    for (idx, frame) in frames.iter().enumerate() {
+        let position = idx + 1;
+        let timestamp = |idx: usize| frame.get(idx)
+            .and_then(Value::as_i64)
+            .and_then(|secs| Local.timestamp_opt(secs, 0).single());
+
+        let (start, stop) = match (timestamp(0), timestamp(1)) {
+            (Some(start), Some(stop)) if stop >= start => (start, stop),
+            _ => {
+                import.skip(position, format!("invalid frame interval `{}`", frame));
+                continue;
+            },
+        };
+
+        let project = frame.get(2).and_then(Value::as_str);
+        let tags = frame.get(4)
+            .and_then(Value::as_array)
+            .map(|tags| tags.iter().filter_map(Value::as_str).map(str::to_string).collect::<Vec<_>>())
+            .unwrap_or_default();
+
+        let note = compose_note(&mut import, position, "", project, &tags);
+        report_seconds(&mut import, position, Some(stop - start));
+        import.records.push(Record {
+            start: Some(start),
+            activity: Some(stop - start),
+            rest: None,
+            note,
+        });
+    }
+    Ok(import)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use crate::convert::IssueKind;
+
+    #[test]
+    fn import() {
+        let source = r#"[
+            [1532646221, 1532647721, "tt-core", "a1b2", ["work", "code review"], 1532647722],
+            [1532685600, 1532689200, "meetings", "c3d4", [], 1532689201],
+            [1532689200, 1532685600, "broken", "e5f6", [], 1532689201]
+        ]"#;
+        let import = import_frames(source.as_bytes()).unwrap();
+        assert_eq!(vec![
+            Record {
+                start: Local.timestamp_opt(1_532_646_221, 0).single(),
+                activity: Some(Duration::minutes(25)),
+                rest: None,
+                note: "@tt-core #work #code_review".to_string(),
+            },
+            Record {
+                start: Local.timestamp_opt(1_532_685_600, 0).single(),
+                activity: Some(Duration::minutes(60)),
+                rest: None,
+                note: "@meetings".to_string(),
+            },
+        ], import.records);
+
+        let issues = import.issues.iter()
+            .map(|issue| (issue.position, issue.kind))
+            .collect::<Vec<_>>();
+        assert_eq!(vec![(1, IssueKind::Lossy), (3, IssueKind::Skipped)], issues);
+    }
+}
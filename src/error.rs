@@ -6,4 +6,24 @@ pub enum TimeTrackError {
     CanNotParseRecord {
         source: String,
     },
-}
\ No newline at end of file
+    #[fail(display = "can't parse journal header line: `{}`", source)]
+    CanNotParseHeader {
+        source: String,
+    },
+    #[fail(display = "unsupported journal format version: {}", version)]
+    UnsupportedJournalVersion {
+        version: u32,
+    },
+    #[fail(display = "can't read idle time from: `{}`", source)]
+    CanNotReadIdleTime {
+        source: String,
+    },
+    #[fail(display = "pomodoro {} must be positive", name)]
+    NonPositiveDuration {
+        name: String,
+    },
+    #[fail(display = "can't find record in journal: `{}`", source)]
+    RecordNotFound {
+        source: String,
+    },
+}
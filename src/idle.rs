@@ -0,0 +1,195 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use std::collections::VecDeque;
+use chrono::{DateTime, Duration, Local};
+use crate::error::TimeTrackError;
+use crate::record::Record;
+use crate::journal::{Journal, JournalResult, record_query};
+
+pub trait IdleSource {
+    /// Time since the last user input.
+    fn idle_time(&mut self) -> JournalResult<Duration>;
+}
+
+/// Returns queued idle times, repeating the last one.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct MockIdleSource {
+    idle_times: VecDeque<Duration>,
+}
+
+impl MockIdleSource {
+    pub fn new<I: IntoIterator<Item = Duration>>(idle_times: I) -> Self {
+        MockIdleSource { idle_times: idle_times.into_iter().collect() }
+    }
+
+    pub fn push(&mut self, idle_time: Duration) {
+        self.idle_times.push_back(idle_time);
+    }
+}
+
+impl IdleSource for MockIdleSource {
+    fn idle_time(&mut self) -> JournalResult<Duration> {
+        let idle_time = if self.idle_times.len() > 1 {
+            self.idle_times.pop_front()
+        } else {
+            self.idle_times.front().copied()
+        };
+        Ok(idle_time.unwrap_or_else(Duration::zero))
+    }
+}
+
+fn parse_millis(source: &str) -> Result<Duration, TimeTrackError> {
+    source.trim()
+        .parse::<i64>()
+        .map(Duration::milliseconds)
+        .map_err(|_| TimeTrackError::CanNotReadIdleTime { source: source.trim().to_string() })
+}
+
+/// Runs a command printing the idle time in milliseconds, like `xprintidle`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandIdleSource {
+    program: String,
+    args: Vec<String>,
+}
+
+impl CommandIdleSource {
+    pub fn new<S: Into<String>>(program: S) -> Self {
+        CommandIdleSource { program: program.into(), args: Vec::new() }
+    }
+
+    pub fn xprintidle() -> Self {
+        CommandIdleSource::new("xprintidle")
+    }
+
+    pub fn arg<S: Into<String>>(mut self, arg: S) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+}
+
+impl IdleSource for CommandIdleSource {
+    fn idle_time(&mut self) -> JournalResult<Duration> {
+        let output = Command::new(&self.program).args(&self.args).output()?;
+        if !output.status.success() {
+            return Err(TimeTrackError::CanNotReadIdleTime { source: self.program.clone() }.into());
+        }
+        Ok(parse_millis(&String::from_utf8_lossy(&output.stdout))?)
+    }
+}
+
+/// Reads the idle time in milliseconds from a file kept up to date by
+/// another process.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileIdleSource {
+    path: PathBuf,
+}
+
+impl FileIdleSource {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        FileIdleSource { path: path.into() }
+    }
+}
+
+impl IdleSource for FileIdleSource {
+    fn idle_time(&mut self) -> JournalResult<Duration> {
+        Ok(parse_millis(&fs::read_to_string(&self.path)?)?)
+    }
+}
+
+/// Turns idle spans longer than the threshold into rest of the last record
+/// with a start in the journal. A running record keeps running; a stopped
+/// record only gets the part of the span before its end.
+pub struct IdleTracker<S> {
+    source: S,
+    threshold: Duration,
+    accounted_until: Option<DateTime<Local>>,
+}
+
+impl<S: IdleSource> IdleTracker<S> {
+    pub fn new(source: S) -> Self {
+        IdleTracker {
+            source,
+            threshold: Duration::minutes(5),
+            accounted_until: None,
+        }
+    }
+
+    pub fn with_threshold(mut self, threshold: Duration) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    pub fn source_mut(&mut self) -> &mut S {
+        &mut self.source
+    }
+
+    /// Checks the idle source and returns the rest added to the running record.
+    pub fn poll<J: Journal>(&mut self, journal: &mut J, now: DateTime<Local>) -> JournalResult<Option<Duration>> {
+        let idle_time = self.source.idle_time()?;
+        if idle_time < self.threshold {
+            self.accounted_until = None;
+            return Ok(None);
+        }
+
+        let running = match journal.records()?.into_iter().rev().find(|record| record.start.is_some()) {
+            Some(record) => record,
+            None => return Ok(None),
+        };
+        let idle_since = now - idle_time;
+        let until = match running.activity {
+            None => now,
+            Some(_) => running.end().unwrap_or(now).min(now),
+        };
+        let from = self.accounted_until.map(|accounted| accounted.max(idle_since)).unwrap_or(idle_since);
+        let from = from.max(running.start.unwrap_or(from));
+        // The journal keeps rest in whole minutes, the remainder waits for the next poll.
+        let added = Duration::minutes((until - from).num_minutes());
+        if added <= Duration::zero() {
+            return Ok(None);
+        }
+
+        let updated = journal.update(&record_query(&running), None, |record| {
+            Some(Record {
+                activity: record.activity.map(|activity| activity - added),
+                rest: Some(record.rest.unwrap_or_else(Duration::zero) + added),
+                ..record
+            })
+        })?;
+        if !updated {
+            return Ok(None);
+        }
+        self.accounted_until = Some(from + added);
+        Ok(Some(added))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mock_source() {
+        let mut source = MockIdleSource::new(vec![Duration::seconds(1), Duration::seconds(2)]);
+        assert_eq!(Duration::seconds(1), source.idle_time().unwrap());
+        assert_eq!(Duration::seconds(2), source.idle_time().unwrap());
+        assert_eq!(Duration::seconds(2), source.idle_time().unwrap());
+        source.push(Duration::seconds(3));
+        assert_eq!(Duration::seconds(2), source.idle_time().unwrap());
+        assert_eq!(Duration::seconds(3), source.idle_time().unwrap());
+        assert_eq!(Duration::zero(), MockIdleSource::default().idle_time().unwrap());
+    }
+
+    #[test]
+    fn millis() {
+        assert_eq!(Ok(Duration::milliseconds(61_500)), parse_millis("61500\n"));
+        assert!(parse_millis("idle").is_err());
+    }
+
+    #[test]
+    fn command_source() {
+        let mut source = CommandIdleSource::new("echo").arg("1500");
+        assert_eq!(Duration::milliseconds(1500), source.idle_time().unwrap());
+        assert!(CommandIdleSource::new("false").idle_time().is_err());
+    }
+}
@@ -1,15 +1,86 @@
 pub mod file;
+pub mod sharded;
+pub mod multi;
+pub mod merge;
+pub mod dedupe;
+#[cfg(feature = "json")]
+pub mod json_lines;
 
 use failure::Error;
 use crate::record::{Record, RecordFieldType};
 
 pub type JournalResult<T = ()> = Result<T, Error>;
 
+/// Records are selected by a query and an offset from the first matching
+/// record; a negative offset from the first record counts from the end. What an
+/// offset counts depends on the backend: `FileJournal` and the journals
+/// built on it count lines of the file, so an offset can land on a comment
+/// and find nothing, while `ShardedJournal` and `MultiJournal` count records.
 pub trait Journal {
     fn add(&mut self, record: &Record) -> JournalResult;
     fn get(&self, query: &[RecordFieldType], offset: Option<i32>) -> JournalResult<Option<Record>>;
+    /// Returns all records in journal order.
+    fn records(&self) -> JournalResult<Vec<Record>>;
     fn update<F>(&mut self, query: &[RecordFieldType], offset: Option<i32>, f: F) -> JournalResult<bool>
         where F: FnOnce(Record) -> Option<Record>;
     fn remove<F>(&mut self, query: &[RecordFieldType], offset: Option<i32>, f: F) -> JournalResult<bool>
         where F: FnOnce(Record) -> bool;
-}
\ No newline at end of file
+
+    /// Removes the records at the positions in `records` and returns them.
+    /// The default removes them one by one, addressing each by its fields
+    /// and an offset counted in records; journals with line offsets override it.
+    fn remove_records(&mut self, positions: &[usize]) -> JournalResult<Vec<Record>> {
+        let records = self.records()?;
+        let mut positions = positions.to_vec();
+        positions.sort_unstable();
+        positions.dedup();
+        let mut removed = Vec::new();
+        for &position in positions.iter().rev() {
+            let record = match records.get(position) {
+                Some(record) => record,
+                None => continue,
+            };
+            let first = records.iter().position(|other| other == record).unwrap_or(position);
+            if self.remove(&record_query(record), Some((position - first) as i32), |_| true)? {
+                removed.push(record.clone());
+            }
+        }
+        removed.reverse();
+        Ok(removed)
+    }
+}
+
+/// Query matching every field of the record.
+pub(crate) fn record_query(record: &Record) -> [RecordFieldType; 4] {
+    [
+        RecordFieldType::Start(record.start),
+        RecordFieldType::Activity(record.activity),
+        RecordFieldType::Rest(record.rest),
+        RecordFieldType::Note(record.note.clone()),
+    ]
+}
+
+pub(crate) fn matches_query(record: &Record, query: &[RecordFieldType]) -> bool {
+    query.iter().all(|field| match field {
+        RecordFieldType::Start(x) => *x == record.start,
+        RecordFieldType::Activity(x) => *x == record.activity,
+        RecordFieldType::Rest(x) => *x == record.rest,
+        RecordFieldType::Note(x) => *x == record.note,
+    })
+}
+
+/// Resolves a query offset over a list of records the way `FileJournal`
+/// does: a negative offset from the first record counts from the end.
+pub(crate) fn offset_index(len: usize, matched: usize, offset: Option<i32>) -> Option<usize> {
+    let offset = offset.unwrap_or(0) as i64;
+    let target = if matched == 0 && offset < 0 {
+        len as i64 + offset
+    } else {
+        matched as i64 + offset
+    };
+    if target < 0 || target >= len as i64 {
+        None
+    } else {
+        Some(target as usize)
+    }
+}
@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+use crate::record::Record;
+use crate::journal::{Journal, JournalResult};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Equality {
+    #[default]
+    Exact,
+    Start,
+    StartAndNote,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Keep {
+    #[default]
+    First,
+    Newest,
+    Longest,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Dedupe {
+    equality: Equality,
+    keep: Keep,
+}
+
+impl Dedupe {
+    pub fn new() -> Self {
+        Dedupe::default()
+    }
+
+    pub fn with_equality(mut self, equality: Equality) -> Self {
+        self.equality = equality;
+        self
+    }
+
+    pub fn with_keep(mut self, keep: Keep) -> Self {
+        self.keep = keep;
+        self
+    }
+
+    /// Returns the indices of duplicate records to remove, in journal order.
+    /// The newest copy is the one added last.
+    pub fn duplicates(&self, records: &[Record]) -> Vec<usize> {
+        let mut groups: HashMap<_, Vec<usize>> = HashMap::new();
+        for (idx, record) in records.iter().enumerate() {
+            let key = match self.equality {
+                Equality::Exact => (record.start, Some((record.activity, record.rest)), Some(&record.note)),
+                Equality::Start if record.start.is_some() => (record.start, None, None),
+                Equality::StartAndNote if record.start.is_some() => (record.start, None, Some(&record.note)),
+                _ => continue,
+            };
+            groups.entry(key).or_default().push(idx);
+        }
+
+        let mut duplicates = Vec::new();
+        for group in groups.values().filter(|group| group.len() > 1) {
+            let kept = match self.keep {
+                Keep::First => group[0],
+                Keep::Newest => group[group.len() - 1],
+                Keep::Longest => group.iter()
+                    .copied()
+                    .fold(group[0], |longest, idx| {
+                        if records[idx].activity > records[longest].activity { idx } else { longest }
+                    }),
+            };
+            duplicates.extend(group.iter().copied().filter(|&idx| idx != kept));
+        }
+        duplicates.sort_unstable();
+        duplicates
+    }
+
+    /// Removes duplicate records from the journal and returns them.
+    pub fn apply<J: Journal>(&self, journal: &mut J) -> JournalResult<Vec<Record>> {
+        let duplicates = self.duplicates(&journal.records()?);
+        journal.remove_records(&duplicates)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::tests::record;
+
+    fn records() -> Vec<Record> {
+        vec![
+            record("2018-08-16 09:00:00", Some(30), None, "Standup"),
+            record("2018-08-16 09:00:00", Some(30), None, "Standup"),
+            record("2018-08-16 09:00:00", Some(35), None, "Standup"),
+            record("2018-08-16 09:00:00", Some(20), None, "Standup call"),
+            record("", Some(5), None, "Undated"),
+            record("", Some(5), None, "Undated"),
+        ]
+    }
+
+    #[test]
+    fn duplicates() {
+        let records = records();
+        assert_eq!(vec![1, 5], Dedupe::new().duplicates(&records));
+        assert_eq!(vec![0, 1, 3], Dedupe::new().with_equality(Equality::Start).with_keep(Keep::Longest).duplicates(&records));
+        assert_eq!(vec![0, 1], Dedupe::new().with_equality(Equality::StartAndNote).with_keep(Keep::Newest).duplicates(&records));
+        assert_eq!(vec![1, 2, 3], Dedupe::new().with_equality(Equality::Start).duplicates(&records));
+    }
+}
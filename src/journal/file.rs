@@ -1,39 +1,419 @@
 mod iter;
+pub mod history;
+pub mod header;
+pub mod migration;
+pub mod bulk;
 
-use std::fs::OpenOptions;
+use std::fs::{self, OpenOptions};
 use std::ffi::{OsString, OsStr};
+use std::path::{Path, PathBuf};
 use std::io::{Write, BufReader};
+use std::sync::Arc;
+use std::mem;
 use ropey::Rope;
+use chrono::{DateTime, Local};
 use crate::record::{Record, RecordFieldType};
+use crate::record::format::{RecordFormat, BracketFormat};
 use crate::journal::{Journal, JournalResult};
+use crate::journal::merge::{self, MergeResult};
 pub use self::iter::*;
+use self::history::{History, Snapshot, DiffLine, diff_lines};
+use self::header::Header;
+use self::migration::Migration;
+use self::bulk::{BulkEdit, Change};
 
 pub struct FileJournal {
     path: OsString,
+    history: Option<History>,
+    format: Arc<dyn RecordFormat>,
+    section: Option<String>,
+    header: Option<Header>,
+}
+
+/// Writes the content to a temporary file and renames it over the journal,
+/// so an interrupted write never leaves a truncated journal.
+fn write_content(path: &OsStr, content: &str) -> JournalResult {
+    let mut temp = path.to_os_string();
+    temp.push(".tt_tmp");
+    fs::write(&temp, content)?;
+    if let Err(err) = fs::rename(&temp, path) {
+        fs::remove_file(&temp)?;
+        return Err(err.into());
+    }
+    Ok(())
 }
 
 impl FileJournal {
     pub fn new<P: Into<OsString>>(path: P) -> Self {
         FileJournal {
             path: path.into(),
+            history: None,
+            format: Arc::new(BracketFormat::default()),
+            section: None,
+            header: None,
         }
     }
 
+    /// Creates a journal and reads the header of an existing file.
+    pub fn open<P: Into<OsString>>(path: P) -> JournalResult<Self> {
+        let mut journal = FileJournal::new(path);
+        journal.header = journal.read_header()?;
+        Ok(journal)
+    }
+
+    /// Sets the header written with the first change of a file without one.
+    pub fn with_header(mut self, header: Header) -> Self {
+        self.header = Some(header);
+        self
+    }
+
+    pub fn header(&self) -> Option<&Header> {
+        self.header.as_ref()
+    }
+
+    fn read_header(&self) -> JournalResult<Option<Header>> {
+        if Path::new(&self.path).exists() {
+            Ok(Header::parse(&fs::read_to_string(&self.path)?)?)
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn current_header(&self) -> JournalResult<Option<Header>> {
+        match self.header {
+            Some(ref header) => Ok(Some(header.clone())),
+            None => self.read_header(),
+        }
+    }
+
+    /// The configured header if the existing file has none yet; it's written
+    /// together with the first change to the file.
+    fn missing_header(&self) -> JournalResult<Option<String>> {
+        match self.header {
+            Some(ref header) if Path::new(&self.path).exists() && self.read_header()?.is_none() =>
+                Ok(Some(header.to_string())),
+            _ => Ok(None),
+        }
+    }
+
+    /// Iterator for an edit of the file, with the missing header in front.
+    fn edit_iter(&self) -> JournalResult<Iter> {
+        let iter = self.try_iter()?;
+        Ok(match self.missing_header()? {
+            Some(header) => iter.with_rope(Rope::from_str(&(header + &fs::read_to_string(&self.path)?))),
+            None => iter,
+        })
+    }
+
+    /// Writes the header to the file, replacing the existing one.
+    pub fn set_header(&mut self, header: Header) -> JournalResult {
+        let lines = if Path::new(&self.path).exists() {
+            fs::read_to_string(&self.path)?.lines().map(str::to_string).collect()
+        } else {
+            Vec::new()
+        };
+        self.header = Some(header);
+        self.replace_lines(lines)
+    }
+
+    pub fn with_format<F: RecordFormat + 'static>(mut self, format: F) -> Self {
+        self.format = Arc::new(format);
+        self
+    }
+
+    pub fn with_history(mut self, history: History) -> Self {
+        self.history = Some(history);
+        self
+    }
+
+    /// Restricts the journal to records of the given section. Added records
+    /// are appended under the section header.
+    pub fn with_section<S: Into<String>>(mut self, section: S) -> Self {
+        self.section = Some(section.into());
+        self
+    }
+
+    pub fn section(&self) -> Option<&str> {
+        self.section.as_deref()
+    }
+
     pub fn path(&self) -> &OsStr {
         self.path.as_os_str()
     }
 
+    pub fn format(&self) -> &dyn RecordFormat {
+        self.format.as_ref()
+    }
+
+    pub fn history(&self) -> Option<&History> {
+        self.history.as_ref()
+    }
+
+    pub fn snapshot(&self) -> JournalResult<Option<Snapshot>> {
+        if let Some(ref history) = self.history {
+            history.take(Path::new(&self.path))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn snapshots(&self) -> JournalResult<Vec<Snapshot>> {
+        if let Some(ref history) = self.history {
+            history.list(Path::new(&self.path))
+        } else {
+            Ok(Vec::new())
+        }
+    }
+
+    pub fn diff_snapshot(&self, snapshot: &Snapshot) -> JournalResult<Vec<DiffLine>> {
+        let current = if Path::new(&self.path).exists() {
+            fs::read_to_string(&self.path)?
+        } else {
+            String::new()
+        };
+        Ok(diff_lines(&snapshot.content()?, &current))
+    }
+
+    /// Rewrites the file with the given lines, keeping the journal header.
+    pub fn replace_lines<I: IntoIterator<Item = String>>(&mut self, lines: I) -> JournalResult {
+        let header = self.current_header()?;
+        self.snapshot()?;
+        let mut content = header.map(|header| header.to_string()).unwrap_or_default();
+        for line in lines.into_iter().skip_while(|line| Header::is_header_line(line)) {
+            content += &line;
+            content += "\n";
+        }
+        self.write_content(&content)
+    }
+
+    fn write_content(&self, content: &str) -> JournalResult {
+        write_content(&self.path, content)
+    }
+
+    /// Returns the journal lines with records stably sorted by start within
+    /// their section and rewritten by the journal format. Other lines are
+    /// kept as they are and stay attached to the record following them.
+    pub fn normalized_lines(&self) -> JournalResult<Vec<String>> {
+        fn sorted(blocks: &mut Vec<(Option<DateTime<Local>>, Vec<String>)>) -> Vec<String> {
+            blocks.sort_by_key(|(start, _)| *start);
+            blocks.drain(..).flat_map(|(_, lines)| lines).collect()
+        }
+
+        let mut lines = self.current_header()?.map(|header| header.lines()).unwrap_or_default();
+        let mut blocks = Vec::new();
+        let mut pending = Vec::new();
+        let mut iter = self.try_iter()?;
+        let mut in_header = true;
+        while let Some(item) = iter.next() {
+            let line = iter.line().unwrap_or_default();
+            in_header = in_header && Header::is_header_line(&line);
+            match item {
+                _ if in_header => (),
+                Item::Record(record) => {
+                    pending.push(self.format.format_record(&record));
+                    blocks.push((record.start, mem::take(&mut pending)));
+                },
+                Item::Section(_) => {
+                    lines.extend(sorted(&mut blocks));
+                    lines.append(&mut pending);
+                    lines.push(line);
+                },
+                _ => pending.push(line),
+            }
+        }
+        lines.extend(sorted(&mut blocks));
+        lines.append(&mut pending);
+        Ok(lines)
+    }
+
+    pub fn normalize<F>(&mut self, confirm: F) -> JournalResult<bool>
+        where F: FnOnce(&[DiffLine]) -> bool,
+    {
+        let current = fs::read_to_string(&self.path)?;
+        let lines = self.normalized_lines()?;
+        let changes = diff_lines(&current, &lines.join("\n"));
+        if changes.iter().all(|line| matches!(line, DiffLine::Same(_))) || !confirm(&changes) {
+            return Ok(false);
+        }
+        self.replace_lines(lines)?;
+        Ok(true)
+    }
+
+    pub fn migration(&self) -> JournalResult<Migration> {
+        Ok(Migration::plan(&fs::read_to_string(&self.path)?)?)
+    }
+
+    /// Upgrades the file to the current format, keeping the original as
+    /// `<journal>.bak`, or `<journal>.bak.<n>` if that already exists.
+    /// Returns the backup path if the file was changed.
+    pub fn migrate(&mut self) -> JournalResult<Option<PathBuf>> {
+        let migration = self.migration()?;
+        if !migration.is_needed() {
+            return Ok(None);
+        }
+        let mut backup = self.path.clone();
+        backup.push(".bak");
+        let mut count = 0;
+        while Path::new(&backup).exists() {
+            count += 1;
+            backup = self.path.clone();
+            backup.push(format!(".bak.{}", count));
+        }
+        fs::copy(&self.path, &backup)?;
+
+        self.header = Some(migration.header);
+        self.format = Arc::new(BracketFormat::default());
+        self.replace_lines(migration.lines)?;
+        Ok(Some(backup.into()))
+    }
+
+    /// Merges the base and their copy of the journal into this one. Records
+    /// in conflict keep our version and are returned in the result.
+    pub fn merge_with<B: AsRef<Path>, T: AsRef<Path>>(&mut self, base: B, theirs: T) -> JournalResult<MergeResult> {
+        let result = merge::merge(
+            &fs::read_to_string(base)?,
+            &fs::read_to_string(&self.path)?,
+            &fs::read_to_string(theirs)?,
+            self.format(),
+        );
+        self.replace_lines(result.lines.iter().cloned())?;
+        Ok(result)
+    }
+
+    /// Splits the found record at the time into two records in one edit.
+    pub fn split_record(&mut self, query: &[RecordFieldType], offset: Option<i32>, time: DateTime<Local>) -> JournalResult<bool> {
+        let mut iter = self.edit_iter()?;
+        let (first, second) = match iter.go_to_record(query, offset).and_then(|record| record.split_at(time)) {
+            Some(records) => records,
+            None => return Ok(false),
+        };
+        if iter.update(&Item::Record(first)).is_none() || iter.insert(&Item::Record(second)).is_none() {
+            return Ok(false);
+        }
+        self.snapshot()?;
+        iter.flush()?;
+        Ok(true)
+    }
+
+    /// Joins the found record with the next record in one edit. Lines
+    /// between the records are kept.
+    pub fn merge_records(&mut self, query: &[RecordFieldType], offset: Option<i32>) -> JournalResult<bool> {
+        let mut iter = self.edit_iter()?;
+        let first = match iter.go_to_record(query, offset) {
+            Some(record) => record,
+            None => return Ok(false),
+        };
+        let mut steps = 0;
+        let next = loop {
+            steps += 1;
+            match iter.next() {
+                Some(Item::Record(record)) => break record,
+                Some(_) => continue,
+                None => return Ok(false),
+            }
+        };
+        let merged = match first.merge(&next) {
+            Some(merged) => merged,
+            None => return Ok(false),
+        };
+        if iter.update(&Item::Record(merged)).is_none() || iter.backward(steps).get().is_none() || iter.remove().is_none() {
+            return Ok(false);
+        }
+        self.snapshot()?;
+        iter.flush()?;
+        Ok(true)
+    }
+
+    fn run_bulk_edit(&self, edit: &BulkEdit, write: bool) -> JournalResult<Vec<Change>> {
+        let mut iter = if write { self.edit_iter()? } else { self.try_iter()? };
+        // Lines are numbered as in the file before the edit.
+        let header_lines = match self.missing_header()? {
+            Some(header) if write => header.lines().count(),
+            _ => 0,
+        };
+        let mut changes = Vec::new();
+        let mut section = None;
+        let mut line = 0;
+        while let Some(item) = iter.next() {
+            line += 1;
+            let before = match item {
+                Item::Record(record) => record,
+                Item::Section(title) => {
+                    section = Some(title);
+                    continue;
+                },
+                _ => continue,
+            };
+            if self.section.is_some() && self.section != section {
+                continue;
+            }
+            if let Some(after) = edit.apply(&before) {
+                if write {
+                    iter.update(&Item::Record(after.clone()));
+                }
+                changes.push(Change { line: line - header_lines, before, after });
+            }
+        }
+        if write && !changes.is_empty() {
+            self.snapshot()?;
+            iter.flush()?;
+        }
+        Ok(changes)
+    }
+
+    /// Returns the changes the bulk edit would make without writing them.
+    pub fn preview_bulk_edit(&self, edit: &BulkEdit) -> JournalResult<Vec<Change>> {
+        self.run_bulk_edit(edit, false)
+    }
+
+    /// Applies the bulk edit in one pass over the journal.
+    pub fn bulk_edit(&mut self, edit: &BulkEdit) -> JournalResult<Vec<Change>> {
+        self.run_bulk_edit(edit, true)
+    }
+
+    pub fn restore_snapshot(&mut self, snapshot: &Snapshot) -> JournalResult {
+        let content = snapshot.content()?;
+        self.snapshot()?;
+        self.write_content(&content)
+    }
+
     pub fn try_iter(&self) -> JournalResult<Iter> {
         let file = OpenOptions::new().read(true).open(&self.path)?;
         let rope = Rope::from_reader(BufReader::new(file))?;
-        Ok(Iter::new(self.path.clone(), rope, None))
+        Ok(Iter::new(self.path.clone(), rope, None)
+            .with_format(self.format.clone())
+            .with_section(self.section.clone()))
     }
 }
 
 impl Journal for FileJournal {
     fn add(&mut self, record: &Record) -> JournalResult {
+        let missing_header = self.missing_header()?;
+        let mut lines = String::new();
+        if let Some(ref header) = self.header {
+            if !Path::new(&self.path).exists() {
+                lines += &header.to_string();
+            }
+        }
+        if let Some(ref section) = self.section {
+            if Path::new(&self.path).exists() {
+                let mut iter = self.edit_iter()?;
+                if iter.go_to_section_end(section) {
+                    self.snapshot()?;
+                    iter.insert(&Item::Record(record.clone()));
+                    return iter.flush();
+                }
+            }
+            lines += &(Item::Section(section.clone()).to_string() + "\n");
+        }
+        lines += &(self.format.format_record(record) + "\n");
+
+        self.snapshot()?;
+        if let Some(header) = missing_header {
+            return self.write_content(&(header + &fs::read_to_string(&self.path)? + &lines));
+        }
         let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
-        file.write((record.to_string() + "\n").as_bytes())?;
+        file.write_all(lines.as_bytes())?;
         Ok(())
     }
 
@@ -42,15 +422,24 @@ impl Journal for FileJournal {
         Ok(iter.go_to_record(query, offset))
     }
 
+    fn records(&self) -> JournalResult<Vec<Record>> {
+        Ok(self.try_iter()?
+            .sections()
+            .filter(|(section, _)| self.section.is_none() || *section == self.section)
+            .filter_map(|(_, item)| item.into_record())
+            .collect())
+    }
+
     fn update<F>(&mut self, query: &[RecordFieldType], offset: Option<i32>, f: F) -> JournalResult<bool>
         where F: FnOnce(Record) -> Option<Record>,
     {
-        let mut iter = self.try_iter()?;
+        let mut iter = self.edit_iter()?;
         let updated = iter.go_to_record(query, offset)
             .and_then(f)
             .map(|new_record| iter.update(&Item::Record(new_record)).is_some())
             .unwrap_or(false);
         if updated {
+            self.snapshot()?;
             iter.flush()?;
         }
         Ok(updated)
@@ -59,11 +448,38 @@ impl Journal for FileJournal {
     fn remove<F>(&mut self, query: &[RecordFieldType], offset: Option<i32>, f: F) -> JournalResult<bool>
         where F: FnOnce(Record) -> bool,
     {
-        let mut iter = self.try_iter()?;
+        let mut iter = self.edit_iter()?;
         let removed = iter.go_to_record(query, offset)
             .map(|record| f(record) && iter.remove().is_some())
             .unwrap_or(false);
         if removed {
+            self.snapshot()?;
+            iter.flush()?;
+        }
+        Ok(removed)
+    }
+
+    /// Removes the records at the positions in one pass and one write.
+    fn remove_records(&mut self, positions: &[usize]) -> JournalResult<Vec<Record>> {
+        let mut iter = self.edit_iter()?;
+        let mut section = None;
+        let mut position = 0;
+        let mut removed = Vec::new();
+        while let Some(item) = iter.next() {
+            match item {
+                Item::Section(title) => section = Some(title),
+                Item::Record(record) if self.section.is_none() || section == self.section => {
+                    if positions.contains(&position) && iter.remove().is_some() {
+                        iter.backward(1);
+                        removed.push(record);
+                    }
+                    position += 1;
+                },
+                _ => (),
+            }
+        }
+        if !removed.is_empty() {
+            self.snapshot()?;
             iter.flush()?;
         }
         Ok(removed)
@@ -0,0 +1,145 @@
+use std::fmt;
+use chrono::{DateTime, Duration, Local};
+use lazy_static::lazy_static;
+use regex::{Regex, Captures};
+use crate::record::{Record, RecordFieldType};
+use crate::journal::matches_query;
+
+lazy_static! {
+    static ref WORD_REGEX: Regex = Regex::new(r"\S+").unwrap();
+}
+
+#[derive(Debug, Clone)]
+enum NoteEdit {
+    Replace(Regex, String),
+    Retag(String, String),
+}
+
+impl NoteEdit {
+    fn apply(&self, note: &str) -> String {
+        match self {
+            NoteEdit::Replace(regex, replacement) => regex.replace_all(note, replacement.as_str()).into_owned(),
+            NoteEdit::Retag(tag, new_tag) => WORD_REGEX
+                .replace_all(note, |caps: &Captures| {
+                    let word = &caps[0];
+                    match word.strip_prefix(Record::TAG_PREFIX) {
+                        Some(word_tag) if word_tag == tag => format!("{}{}", Record::TAG_PREFIX, new_tag),
+                        _ => word.to_string(),
+                    }
+                })
+                .into_owned(),
+        }
+    }
+}
+
+/// Edits applied to every record matching the query and start range.
+#[derive(Default)]
+pub struct BulkEdit {
+    query: Vec<RecordFieldType>,
+    from: Option<DateTime<Local>>,
+    to: Option<DateTime<Local>>,
+    shift: Option<Duration>,
+    note_edits: Vec<NoteEdit>,
+}
+
+impl BulkEdit {
+    pub fn new() -> Self {
+        BulkEdit::default()
+    }
+
+    pub fn with_query(mut self, query: Vec<RecordFieldType>) -> Self {
+        self.query = query;
+        self
+    }
+
+    /// Selects records starting in `[from, to)`.
+    pub fn between(mut self, from: Option<DateTime<Local>>, to: Option<DateTime<Local>>) -> Self {
+        self.from = from;
+        self.to = to;
+        self
+    }
+
+    pub fn shift(mut self, shift: Duration) -> Self {
+        self.shift = Some(shift);
+        self
+    }
+
+    pub fn replace_note<S: Into<String>>(mut self, regex: Regex, replacement: S) -> Self {
+        self.note_edits.push(NoteEdit::Replace(regex, replacement.into()));
+        self
+    }
+
+    pub fn retag(mut self, tag: &str, new_tag: &str) -> Self {
+        self.note_edits.push(NoteEdit::Retag(tag.to_string(), new_tag.to_string()));
+        self
+    }
+
+    pub fn rename(self, note: &str, new_note: &str) -> Self {
+        let regex = Regex::new(&format!("^{}$", regex::escape(note))).unwrap();
+        self.replace_note(regex, new_note.replace('$', "$$"))
+    }
+
+    pub fn selects(&self, record: &Record) -> bool {
+        matches_query(record, &self.query)
+            && self.from.map(|from| record.start.map(|start| start >= from).unwrap_or(false)).unwrap_or(true)
+            && self.to.map(|to| record.start.map(|start| start < to).unwrap_or(false)).unwrap_or(true)
+    }
+
+    /// Returns the edited record if it is selected and changed.
+    pub fn apply(&self, record: &Record) -> Option<Record> {
+        if !self.selects(record) {
+            return None;
+        }
+        let note = self.note_edits.iter().fold(record.note.clone(), |note, edit| edit.apply(&note));
+        let edited = Record {
+            start: record.start.map(|start| start + self.shift.unwrap_or_else(Duration::zero)),
+            note,
+            ..record.clone()
+        };
+        if edited == *record { None } else { Some(edited) }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Change {
+    /// One-based line number in the journal file.
+    pub line: usize,
+    pub before: Record,
+    pub after: Record,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {} -> {}", self.line, self.before.to_string(), self.after.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::tests::record;
+
+    #[test]
+    fn apply() {
+        let from = record("2018-08-16 00:00:00", None, None, "").start;
+        let to = record("2018-08-17 00:00:00", None, None, "").start;
+        let edit = BulkEdit::new()
+            .between(from, to)
+            .shift(Duration::hours(-1))
+            .retag("wrok", "work");
+
+        let edited = edit.apply(&record("2018-08-16 10:00:00", Some(30), None, "#wrok #wrok  Review #wroking")).unwrap();
+        assert_eq!(record("2018-08-16 09:00:00", None, None, "").start, edited.start);
+        assert_eq!(Some(Duration::minutes(30)), edited.activity);
+        assert_eq!("#work #work  Review #wroking", edited.note);
+        assert_eq!(None, edit.apply(&record("2018-08-17 10:00:00", Some(30), None, "#wrok")));
+        assert_eq!(None, edit.apply(&record("", Some(30), None, "#wrok")));
+
+        let edit = BulkEdit::new()
+            .with_query(vec![RecordFieldType::Activity(Some(Duration::minutes(30)))])
+            .rename("Reveiw", "Review $1");
+        assert_eq!("Review $1", edit.apply(&record("", Some(30), None, "Reveiw")).unwrap().note);
+        assert_eq!(None, edit.apply(&record("", Some(30), None, "Reveiw later")));
+        assert_eq!(None, edit.apply(&record("", Some(20), None, "Reveiw")));
+    }
+}
@@ -0,0 +1,176 @@
+use std::fmt;
+use std::str::FromStr;
+use chrono::FixedOffset;
+use crate::error::TimeTrackError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Precision {
+    #[default]
+    Minutes,
+    Seconds,
+}
+
+impl fmt::Display for Precision {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Precision::Minutes => "minutes",
+            Precision::Seconds => "seconds",
+        })
+    }
+}
+
+impl FromStr for Precision {
+    type Err = TimeTrackError;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        match source {
+            "minutes" => Ok(Precision::Minutes),
+            "seconds" => Ok(Precision::Seconds),
+            _ => Err(TimeTrackError::CanNotParseHeader { source: source.to_string() }),
+        }
+    }
+}
+
+/// Metadata block of `#! key: value` lines at the top of a journal file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Header {
+    pub version: u32,
+    pub timezone: Option<FixedOffset>,
+    pub precision: Option<Precision>,
+    pub owner: Option<String>,
+    /// Lines with unknown keys, written back as they are.
+    pub other_lines: Vec<String>,
+}
+
+impl Default for Header {
+    fn default() -> Self {
+        Header {
+            version: Header::CURRENT_VERSION,
+            timezone: None,
+            precision: None,
+            owner: None,
+            other_lines: Vec::new(),
+        }
+    }
+}
+
+impl Header {
+    pub const PREFIX: &'static str = "#!";
+    pub const CURRENT_VERSION: u32 = 1;
+
+    pub fn with_timezone(mut self, timezone: FixedOffset) -> Self {
+        self.timezone = Some(timezone);
+        self
+    }
+
+    pub fn with_precision(mut self, precision: Precision) -> Self {
+        self.precision = Some(precision);
+        self
+    }
+
+    pub fn with_owner<S: Into<String>>(mut self, owner: S) -> Self {
+        self.owner = Some(owner.into());
+        self
+    }
+
+    pub fn is_header_line(line: &str) -> bool {
+        line.starts_with(Header::PREFIX)
+    }
+
+    /// Parses the header block at the start of the content, if there is one.
+    pub fn parse(content: &str) -> Result<Option<Header>, TimeTrackError> {
+        let mut lines = content.lines().take_while(|line| Header::is_header_line(line)).peekable();
+        if lines.peek().is_none() {
+            return Ok(None);
+        }
+
+        let mut header = Header::default();
+        for line in lines {
+            let error = || TimeTrackError::CanNotParseHeader { source: line.to_string() };
+            let mut parts = line[Header::PREFIX.len()..].splitn(2, ':');
+            let key = parts.next().unwrap_or_default().trim();
+            let value = parts.next().ok_or_else(error)?.trim();
+            match key {
+                "version" => header.version = value.parse().map_err(|_| error())?,
+                "timezone" => header.timezone = Some(value.parse().map_err(|_| error())?),
+                "precision" => header.precision = Some(value.parse().map_err(|_| error())?),
+                "owner" => header.owner = Some(value.to_string()),
+                _ => header.other_lines.push(line.to_string()),
+            }
+        }
+        Ok(Some(header))
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![
+            format!("{} version: {}", Header::PREFIX, self.version),
+        ];
+        if let Some(timezone) = self.timezone {
+            lines.push(format!("{} timezone: {}", Header::PREFIX, timezone));
+        }
+        if let Some(precision) = self.precision {
+            lines.push(format!("{} precision: {}", Header::PREFIX, precision));
+        }
+        if let Some(ref owner) = self.owner {
+            lines.push(format!("{} owner: {}", Header::PREFIX, owner));
+        }
+        lines.extend(self.other_lines.iter().cloned());
+        lines
+    }
+}
+
+impl fmt::Display for Header {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for line in self.lines() {
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_header() {
+        assert_eq!(Ok(None), Header::parse("[, ] Note\n"));
+        assert_eq!(Ok(None), Header::parse("[, ] Note\n#! version: 2\n"));
+
+        let header = Header::parse("#! version: 2\n#!timezone: +03:00\n#! owner: Jo Doe\n#! other: x\n[, ] Note\n")
+            .unwrap()
+            .unwrap();
+        assert_eq!(Header {
+            version: 2,
+            timezone: FixedOffset::east_opt(3 * 3600),
+            precision: None,
+            owner: Some("Jo Doe".to_string()),
+            other_lines: vec!["#! other: x".to_string()],
+        }, header);
+
+        assert!(Header::parse("#! version: two\n").is_err());
+        assert!(Header::parse("#! precision: hours\n").is_err());
+        assert!(Header::parse("#! owner\n").is_err());
+    }
+
+    #[test]
+    fn format_header() {
+        let header = Header::default()
+            .with_timezone(FixedOffset::west_opt(5 * 3600).unwrap())
+            .with_precision(Precision::Seconds)
+            .with_owner("Jo Doe");
+        assert_eq!("#! version: 1
+#! timezone: -05:00
+#! precision: seconds
+#! owner: Jo Doe
+", header.to_string());
+        assert_eq!(Ok(Some(header.clone())), Header::parse(&header.to_string()));
+        assert_eq!("#! version: 1\n", Header::default().to_string());
+    }
+
+    #[test]
+    fn round_trip() {
+        let source = "#! version: 1\n#! timezone: +03:00\n#! precision: minutes\n#! owner: Jo Doe\n#!editor:  vim\n#! sync: daily\n";
+        assert_eq!(source, Header::parse(source).unwrap().unwrap().to_string());
+    }
+}
@@ -0,0 +1,359 @@
+use std::fs;
+use std::fmt;
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::env;
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Local, NaiveDateTime, NaiveDate, TimeZone, Duration};
+use crate::journal::JournalResult;
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RetentionPolicy {
+    pub keep_last: Option<usize>,
+    pub keep_daily_for: Option<u32>,
+}
+
+impl RetentionPolicy {
+    pub fn keep_last(mut self, count: usize) -> Self {
+        self.keep_last = Some(count);
+        self
+    }
+
+    pub fn keep_daily_for(mut self, days: u32) -> Self {
+        self.keep_daily_for = Some(days);
+        self
+    }
+
+    pub fn retained<'a>(&self, snapshots: &'a [Snapshot], now: DateTime<Local>) -> Vec<&'a Snapshot> {
+        if self.keep_last.is_none() && self.keep_daily_for.is_none() {
+            return snapshots.iter().collect();
+        }
+
+        let mut newest_first = snapshots.iter().collect::<Vec<_>>();
+        newest_first.sort_by(|a, b| b.cmp(a));
+
+        let mut kept = HashSet::new();
+        if let Some(count) = self.keep_last {
+            kept.extend(newest_first.iter().take(count).map(|snapshot| snapshot.path.clone()));
+        }
+        if let Some(days) = self.keep_daily_for {
+            let today = now.naive_local().date();
+            let mut seen_days = HashSet::new();
+            for snapshot in &newest_first {
+                let day = snapshot.day();
+                if today - day < Duration::days(i64::from(days)) && seen_days.insert(day) {
+                    kept.insert(snapshot.path.clone());
+                }
+            }
+        }
+
+        snapshots.iter().filter(|snapshot| kept.contains(&snapshot.path)).collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    pub path: PathBuf,
+    pub created: DateTime<Local>,
+}
+
+impl Snapshot {
+    const TIMESTAMP_FORMAT: &'static str = "%Y%m%d-%H%M%S";
+
+    /// File name prefix of the journal snapshots: the journal file name and
+    /// a hash of its absolute path, so journals with the same name don't mix.
+    fn prefix(journal: &Path) -> String {
+        let absolute = journal.canonicalize().unwrap_or_else(|_| {
+            env::current_dir().map(|dir| dir.join(journal)).unwrap_or_else(|_| journal.to_path_buf())
+        });
+        // FNV-1a keeps the names stable across builds, unlike `DefaultHasher`
+        let hash = absolute.to_string_lossy()
+            .bytes()
+            .fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3));
+        format!("{}.{:016x}.", journal.file_name().unwrap_or_default().to_string_lossy(), hash)
+    }
+
+    fn file_name(prefix: &str, created: &DateTime<Local>) -> String {
+        format!(
+            "{}{}.{:09}",
+            prefix,
+            created.format(Snapshot::TIMESTAMP_FORMAT),
+            created.timestamp_subsec_nanos()
+        )
+    }
+
+    fn from_path(prefix: &str, path: PathBuf) -> Option<Self> {
+        let created = {
+            let name = path.file_name()?.to_str()?;
+            let suffix = name.get(prefix.len()..).filter(|_| name.starts_with(prefix))?;
+            let mut parts = suffix.rsplitn(2, '.');
+            let nanos = parts.next()?.parse::<u32>().ok()?;
+            let naive = NaiveDateTime::parse_from_str(parts.next()?, Snapshot::TIMESTAMP_FORMAT).ok()?;
+            Local.from_local_datetime(&naive).earliest()? + Duration::nanoseconds(i64::from(nanos))
+        };
+        Some(Snapshot { path, created })
+    }
+
+    pub fn day(&self) -> NaiveDate {
+        self.created.naive_local().date()
+    }
+
+    pub fn content(&self) -> JournalResult<String> {
+        Ok(fs::read_to_string(&self.path)?)
+    }
+}
+
+impl PartialOrd for Snapshot {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Snapshot {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.created.cmp(&other.created).then_with(|| self.path.cmp(&other.path))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct History {
+    dir: PathBuf,
+    retention: RetentionPolicy,
+}
+
+impl History {
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        History {
+            dir: dir.into(),
+            retention: RetentionPolicy::default(),
+        }
+    }
+
+    pub fn with_retention(mut self, retention: RetentionPolicy) -> Self {
+        self.retention = retention;
+        self
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn retention(&self) -> &RetentionPolicy {
+        &self.retention
+    }
+
+    pub fn take(&self, journal: &Path) -> JournalResult<Option<Snapshot>> {
+        if !journal.exists() {
+            return Ok(None);
+        }
+        let prefix = Snapshot::prefix(journal);
+        fs::create_dir_all(&self.dir)?;
+
+        let mut created = Local::now();
+        let mut path = self.dir.join(Snapshot::file_name(&prefix, &created));
+        while path.exists() {
+            created += Duration::nanoseconds(1);
+            path = self.dir.join(Snapshot::file_name(&prefix, &created));
+        }
+        fs::copy(journal, &path)?;
+
+        self.apply_retention(journal, created)?;
+        Ok(Some(Snapshot { path, created }))
+    }
+
+    pub fn list(&self, journal: &Path) -> JournalResult<Vec<Snapshot>> {
+        let prefix = Snapshot::prefix(journal);
+        let mut snapshots = Vec::new();
+        if self.dir.is_dir() {
+            for entry in fs::read_dir(&self.dir)? {
+                if let Some(snapshot) = Snapshot::from_path(&prefix, entry?.path()) {
+                    snapshots.push(snapshot);
+                }
+            }
+        }
+        snapshots.sort();
+        Ok(snapshots)
+    }
+
+    pub fn apply_retention(&self, journal: &Path, now: DateTime<Local>) -> JournalResult<Vec<Snapshot>> {
+        let snapshots = self.list(journal)?;
+        let retained = self.retention.retained(&snapshots, now)
+            .into_iter()
+            .map(|snapshot| snapshot.path.clone())
+            .collect::<HashSet<_>>();
+
+        let mut removed = Vec::new();
+        for snapshot in snapshots {
+            if !retained.contains(&snapshot.path) {
+                fs::remove_file(&snapshot.path)?;
+                removed.push(snapshot);
+            }
+        }
+        Ok(removed)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffLine {
+    Same(String),
+    Added(String),
+    Removed(String),
+}
+
+impl fmt::Display for DiffLine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DiffLine::Same(line) => write!(f, "  {}", line),
+            DiffLine::Added(line) => write!(f, "+ {}", line),
+            DiffLine::Removed(line) => write!(f, "- {}", line),
+        }
+    }
+}
+
+/// Length of the longest common subsequence of `old` and each prefix of `new`.
+fn lcs_lengths<'a, I, J>(old: I, new: J) -> Vec<usize>
+    where I: Iterator<Item = &'a &'a str>,
+          J: Iterator<Item = &'a &'a str> + Clone,
+{
+    let mut row = vec![0usize; new.clone().count() + 1];
+    for old_line in old {
+        let mut diagonal = 0;
+        for (j, new_line) in new.clone().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if old_line == new_line { diagonal + 1 } else { above.max(row[j]) };
+            diagonal = above;
+        }
+    }
+    row
+}
+
+/// Hirschberg's algorithm: the LCS diff in linear space.
+fn diff_slices(old: &[&str], new: &[&str], diff: &mut Vec<DiffLine>) {
+    if old.is_empty() {
+        diff.extend(new.iter().map(|line| DiffLine::Added(line.to_string())));
+    } else if new.is_empty() {
+        diff.extend(old.iter().map(|line| DiffLine::Removed(line.to_string())));
+    } else if old.len() == 1 {
+        match new.iter().position(|line| *line == old[0]) {
+            Some(idx) => {
+                diff.extend(new[..idx].iter().map(|line| DiffLine::Added(line.to_string())));
+                diff.push(DiffLine::Same(old[0].to_string()));
+                diff.extend(new[idx + 1..].iter().map(|line| DiffLine::Added(line.to_string())));
+            },
+            None => {
+                diff.push(DiffLine::Removed(old[0].to_string()));
+                diff.extend(new.iter().map(|line| DiffLine::Added(line.to_string())));
+            },
+        }
+    } else {
+        let mid = old.len() / 2;
+        let forward = lcs_lengths(old[..mid].iter(), new.iter());
+        let backward = lcs_lengths(old[mid..].iter().rev(), new.iter().rev());
+        let split = (0..=new.len())
+            .rev()
+            .max_by_key(|&j| forward[j] + backward[new.len() - j])
+            .unwrap_or(0);
+        diff_slices(&old[..mid], &new[..split], diff);
+        diff_slices(&old[mid..], &new[split..], diff);
+    }
+}
+
+pub fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    let old = old.lines().collect::<Vec<_>>();
+    let new = new.lines().collect::<Vec<_>>();
+
+    let prefix = old.iter().zip(&new).take_while(|(old, new)| old == new).count();
+    let suffix = old[prefix..].iter().rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(old, new)| old == new)
+        .count();
+
+    let mut diff = old[..prefix].iter().map(|line| DiffLine::Same(line.to_string())).collect::<Vec<_>>();
+    diff_slices(&old[prefix..old.len() - suffix], &new[prefix..new.len() - suffix], &mut diff);
+    diff.extend(old[old.len() - suffix..].iter().map(|line| DiffLine::Same(line.to_string())));
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot_at(datetime: &str) -> Snapshot {
+        let created = Local.from_local_datetime(
+            &NaiveDateTime::parse_from_str(datetime, "%Y-%m-%d %H:%M:%S").unwrap()
+        ).unwrap();
+        Snapshot {
+            path: PathBuf::from(Snapshot::file_name("journal.txt.0123456789abcdef.", &created)),
+            created,
+        }
+    }
+
+    #[test]
+    fn snapshot_name_round_trip() {
+        let snapshot = snapshot_at("2018-08-16 13:52:43");
+        let parsed = Snapshot::from_path("journal.txt.0123456789abcdef.", snapshot.path.clone()).unwrap();
+        assert_eq!(snapshot, parsed);
+
+        assert!(Snapshot::from_path("other.txt.0123456789abcdef.", snapshot.path.clone()).is_none());
+        assert!(Snapshot::from_path("journal.txt.fedcba9876543210.", snapshot.path.clone()).is_none());
+        assert!(Snapshot::from_path("journal.txt.0123456789abcdef.", PathBuf::from("journal.txt.tt_back")).is_none());
+    }
+
+    #[test]
+    fn snapshot_prefix() {
+        let first = Snapshot::prefix(Path::new("first/journal.txt"));
+        let second = Snapshot::prefix(Path::new("second/journal.txt"));
+        assert!(first.starts_with("journal.txt."));
+        assert_ne!(first, second);
+        assert_eq!(first, Snapshot::prefix(Path::new("first/journal.txt")));
+    }
+
+    #[test]
+    fn retention() {
+        let snapshots = [
+            snapshot_at("2018-08-14 10:00:00"),
+            snapshot_at("2018-08-15 09:00:00"),
+            snapshot_at("2018-08-15 18:00:00"),
+            snapshot_at("2018-08-16 08:00:00"),
+            snapshot_at("2018-08-16 12:00:00"),
+        ];
+        let now = snapshot_at("2018-08-16 13:00:00").created;
+
+        let retained = RetentionPolicy::default().retained(&snapshots, now);
+        assert_eq!(5, retained.len());
+
+        let retained = RetentionPolicy::default().keep_last(2).retained(&snapshots, now);
+        assert_eq!(vec![&snapshots[3], &snapshots[4]], retained);
+
+        let retained = RetentionPolicy::default().keep_daily_for(2).retained(&snapshots, now);
+        assert_eq!(vec![&snapshots[2], &snapshots[4]], retained);
+
+        let retained = RetentionPolicy::default().keep_last(1).keep_daily_for(3).retained(&snapshots, now);
+        assert_eq!(vec![&snapshots[0], &snapshots[2], &snapshots[4]], retained);
+    }
+
+    #[test]
+    fn diff() {
+        let diff = diff_lines("a\nb\nc\n", "a\nc\nd\n");
+        assert_eq!(vec![
+            DiffLine::Same("a".to_string()),
+            DiffLine::Removed("b".to_string()),
+            DiffLine::Same("c".to_string()),
+            DiffLine::Added("d".to_string()),
+        ], diff);
+
+        let diff = diff_lines("a\nx\nb\nc\ny\nz\n", "a\nb\nq\nc\nz\n");
+        assert_eq!(vec![
+            DiffLine::Same("a".to_string()),
+            DiffLine::Removed("x".to_string()),
+            DiffLine::Same("b".to_string()),
+            DiffLine::Added("q".to_string()),
+            DiffLine::Same("c".to_string()),
+            DiffLine::Removed("y".to_string()),
+            DiffLine::Same("z".to_string()),
+        ], diff);
+
+        assert!(diff_lines("", "").is_empty());
+        assert_eq!(vec![DiffLine::Added("a".to_string())], diff_lines("", "a"));
+    }
+}
@@ -1,17 +1,24 @@
-use std::fs;
 use std::ffi::OsString;
-use std::io::BufWriter;
-use std::str::FromStr;
 use std::borrow::Cow;
+use std::sync::Arc;
 use ropey::Rope;
 use crate::record::{Record, RecordFieldType};
-use crate::journal::JournalResult;
+use crate::record::format::{RecordFormat, BracketFormat};
+use crate::journal::{JournalResult, matches_query};
+use super::header::Header;
 
-#[derive(Default)]
 pub struct Iter {
     path: OsString,
     rope: Rope,
     cur_line_idx: Option<usize>,
+    format: Arc<dyn RecordFormat>,
+    section: Option<String>,
+}
+
+impl Default for Iter {
+    fn default() -> Self {
+        Iter::new(OsString::default(), Rope::default(), None)
+    }
 }
 
 impl Iter {
@@ -20,6 +27,8 @@ impl Iter {
             path,
             rope,
             cur_line_idx,
+            format: Arc::new(BracketFormat::default()),
+            section: None,
         }
     }
 
@@ -28,6 +37,36 @@ impl Iter {
         self
     }
 
+    pub fn with_format(mut self, format: Arc<dyn RecordFormat>) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn format(&self) -> &dyn RecordFormat {
+        self.format.as_ref()
+    }
+
+    /// Restricts record queries to records of the given section.
+    pub fn with_section(mut self, section: Option<String>) -> Self {
+        self.section = section;
+        self
+    }
+
+    pub fn section(&self) -> Option<String> {
+        let cur_line_idx = self.cur_line_idx?.min(self.lines_count());
+        (0..cur_line_idx + 1).rev()
+            .filter_map(|idx| match self.item_at(idx) {
+                Some(Item::Section(title)) => Some(title),
+                _ => None,
+            })
+            .next()
+    }
+
+    pub fn sections(self) -> Sections {
+        let section = self.section();
+        Sections { iter: self, section }
+    }
+
     pub fn lines_count(&self) -> usize {
         let count = self.rope.len_lines();
         if count > 0 && self.rope.line(count - 1).as_str().map(str::is_empty).unwrap_or(true) {
@@ -37,20 +76,33 @@ impl Iter {
         }
     }
 
+    fn item_at(&self, line_idx: usize) -> Option<Item> {
+        if line_idx < self.lines_count() {
+            let line = &Cow::from(
+                self.rope.line(line_idx)
+            );
+            Some(
+                self.format.parse_record(line)
+                    .map(Item::Record)
+                    .unwrap_or_else(|_| Item::from_line(line))
+            )
+        } else {
+            None
+        }
+    }
+
     pub fn get(&self) -> Option<<Self as Iterator>::Item> {
-        if let Some(cur_line_idx) = self.cur_line_idx {
-            if cur_line_idx < self.lines_count() {
-                let line = &Cow::from(
-                    self.rope.line(cur_line_idx)
-                );
-                return Some(
-                    Record::from_str(line)
-                        .map(|r| Item::Record(r))
-                        .unwrap_or(Item::SomeLine(line.trim_right_matches('\n').to_string()))
-                );
-            }
+        self.item_at(self.cur_line_idx?)
+    }
+
+    /// Text of the current line as it is in the file.
+    pub fn line(&self) -> Option<String> {
+        let line_idx = self.cur_line_idx?;
+        if line_idx < self.lines_count() {
+            Some(Cow::from(self.rope.line(line_idx)).trim_end_matches(['\r', '\n']).to_string())
+        } else {
+            None
         }
-        None
     }
 
     pub fn forward(&mut self, n: usize) -> &Self {
@@ -85,22 +137,45 @@ impl Iter {
         self.cur_line_idx = if count > 0 {Some(count)} else {None};
     }
 
+    /// Index of the first section header after the line, or the lines count.
+    fn section_end(&self, line_idx: usize) -> usize {
+        let count = self.lines_count();
+        (line_idx + 1..count)
+            .find(|&idx| matches!(self.item_at(idx), Some(Item::Section(_))))
+            .unwrap_or(count)
+    }
+
+    /// Moves to the last non-empty line of the last section with the title.
+    pub fn go_to_section_end(&mut self, title: &str) -> bool {
+        let header_idx = (0..self.lines_count()).rev()
+            .find(|&idx| matches!(self.item_at(idx), Some(Item::Section(ref section)) if section == title));
+        match header_idx {
+            Some(header_idx) => {
+                let last_idx = (header_idx + 1..self.section_end(header_idx)).rev()
+                    .find(|&idx| !matches!(self.item_at(idx), Some(Item::SomeLine(ref line)) if line.trim().is_empty()))
+                    .unwrap_or(header_idx);
+                self.cur_line_idx = Some(last_idx);
+                true
+            },
+            None => false,
+        }
+    }
+
     pub fn go_to_record(&mut self, query: &[RecordFieldType], offset: Option<i32>) -> Option<Record> {
         let offset = offset.unwrap_or(0);
         let mut first_record = true;
+        let mut section = self.section();
 
         'next_record: while let Some(item) = self.next() {
-            if let Item::Record(record) = item {
-                for field in query {
-                    if !match field {
-                        RecordFieldType::Start(x) => *x == record.start,
-                        RecordFieldType::Activity(x) => *x == record.activity,
-                        RecordFieldType::Rest(x) => *x == record.rest,
-                        RecordFieldType::Note(x) => *x == record.note,
-                    } {
-                        first_record = false;
-                        continue 'next_record;
-                    }
+            if let Item::Section(title) = item {
+                section = Some(title);
+            } else if let Item::Record(record) = item {
+                if self.section.is_some() && self.section != section {
+                    continue 'next_record;
+                }
+                if !matches_query(&record, query) {
+                    first_record = false;
+                    continue 'next_record;
                 }
 
                 return if offset == 0 {
@@ -112,7 +187,10 @@ impl Iter {
                         .and_then(|item| item.into_record())
                 } else {
                     if first_record {
-                        self.go_to_end();
+                        match (self.section.is_some(), self.cur_line_idx) {
+                            (true, Some(idx)) => self.cur_line_idx = Some(self.section_end(idx)),
+                            _ => self.go_to_end(),
+                        }
                     }
                     self
                         .backward(-offset as usize)
@@ -124,9 +202,30 @@ impl Iter {
         None
     }
 
+    fn format_item(&self, item: &<Self as Iterator>::Item) -> String {
+        match item {
+            Item::Record(record) => self.format.format_record(record),
+            _ => item.to_string(),
+        }
+    }
+
     pub fn update(&mut self, item: &<Self as Iterator>::Item) -> Option<usize> {
+        let line = self.format_item(item);
         let start_idx = self.remove()?;
-        self.rope.insert(start_idx, &(item.to_string() + "\n"));
+        self.rope.insert(start_idx, &(line + "\n"));
+        Some(start_idx)
+    }
+
+    /// Inserts the item after the current line and moves to it.
+    pub fn insert(&mut self, item: &<Self as Iterator>::Item) -> Option<usize> {
+        let line_idx = self.cur_line_idx.map(|idx| idx + 1).unwrap_or(0).min(self.lines_count());
+        let start_idx = self.rope.line_to_char(line_idx);
+        let mut line = self.format_item(item) + "\n";
+        if start_idx > 0 && start_idx == self.rope.len_chars() && self.rope.char(start_idx - 1) != '\n' {
+            line.insert(0, '\n');
+        }
+        self.rope.insert(start_idx, &line);
+        self.cur_line_idx = Some(line_idx);
         Some(start_idx)
     }
 
@@ -141,31 +240,42 @@ impl Iter {
     }
 
     pub fn flush(&mut self) -> JournalResult {
-        let mut backup = self.path.clone();
-        backup.push(".tt_back");
-
-        fs::copy(&self.path, &backup)?;
-
-        let file = fs::OpenOptions::new().truncate(true).write(true).open(&self.path)?;
-        let result = if let Err(err) = self.rope.write_to(BufWriter::new(file)) {
-            fs::copy(&backup, &self.path)?;
-            Err(err.into())
-        } else {
-            Ok(())
-        };
-
-        fs::remove_file(&backup)?;
-        result
+        super::write_content(&self.path, &self.rope.to_string())
     }
 }
 
 #[derive(Debug, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum Item {
     Record(Record),
+    Comment(String),
+    Section(String),
     SomeLine(String),
 }
 
 impl Item {
+    pub const COMMENT_PREFIXES: [&'static str; 2] = ["#", "//"];
+    pub const SECTION_MARK: &'static str = "==";
+
+    /// Classifies a line which is not a record.
+    pub fn from_line(line: &str) -> Self {
+        let trimmed = line.trim();
+        if Header::is_header_line(trimmed) {
+            return Item::SomeLine(line.trim_end_matches(['\r', '\n']).to_string());
+        }
+        if let Some(title) = trimmed.strip_prefix(Item::SECTION_MARK)
+            .and_then(|rest| rest.strip_suffix(Item::SECTION_MARK)) {
+            return Item::Section(title.trim().to_string());
+        }
+        for prefix in &Item::COMMENT_PREFIXES {
+            if let Some(text) = trimmed.strip_prefix(prefix) {
+                return Item::Comment(text.trim().to_string());
+            }
+        }
+        Item::SomeLine(line.trim_end_matches(['\r', '\n']).to_string())
+    }
+
     pub fn record(&self) -> Option<&Record> {
         match self {
             Item::Record(r) => Some(r),
@@ -185,11 +295,31 @@ impl ToString for Item {
     fn to_string(&self) -> String {
         match self {
             Item::Record(r) => r.to_string(),
+            Item::Comment(text) if text.is_empty() => Item::COMMENT_PREFIXES[0].to_string(),
+            Item::Comment(text) => format!("{} {}", Item::COMMENT_PREFIXES[0], text),
+            Item::Section(title) => format!("{} {} {}", Item::SECTION_MARK, title, Item::SECTION_MARK),
             Item::SomeLine(s) => s.clone(),
         }
     }
 }
 
+pub struct Sections {
+    iter: Iter,
+    section: Option<String>,
+}
+
+impl Iterator for Sections {
+    type Item = (Option<String>, Item);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.iter.next()?;
+        if let Item::Section(title) = &item {
+            self.section = Some(title.clone());
+        }
+        Some((self.section.clone(), item))
+    }
+}
+
 impl Iterator for Iter {
     type Item = Item;
 
@@ -508,4 +638,67 @@ mod tests {
         iter.go_to_start();
         assert!(iter.next().is_none());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn item_from_line() {
+        assert_eq!(Item::Comment("foo".to_string()), Item::from_line("# foo\n"));
+        assert_eq!(Item::Comment("foo".to_string()), Item::from_line("  //foo"));
+        assert_eq!(Item::Section("2018-08-16".to_string()), Item::from_line("== 2018-08-16 ==\n"));
+        assert_eq!(item_line("== foo"), Item::from_line("== foo\r\n"));
+        assert_eq!(item_line("foo"), Item::from_line("foo"));
+        assert_eq!(item_line("#! version: 1"), Item::from_line("#! version: 1\n"));
+        assert_eq!("# foo", Item::Comment("foo".to_string()).to_string());
+        assert_eq!("== bar ==", Item::Section("bar".to_string()).to_string());
+    }
+
+    #[test]
+    fn iter_sections() {
+        let rope = Rope::from_reader(BufReader::new(Cursor::new(
+            b"[,()] foo\n== one ==\n# note\n[,()] bar\n== two ==\n[,()] bar\n[,()] bazz"
+        ))).unwrap();
+        let items = Iter::default().with_rope(rope.clone())
+            .sections()
+            .filter_map(|(section, item)| item.into_record().map(|record| (section, record.note)))
+            .collect::<Vec<_>>();
+        assert_eq!(vec![
+            (None, "foo".to_string()),
+            (Some("one".to_string()), "bar".to_string()),
+            (Some("two".to_string()), "bar".to_string()),
+            (Some("two".to_string()), "bazz".to_string()),
+        ], items);
+
+        let mut iter = Iter::default().with_rope(rope.clone()).with_section(Some("two".to_string()));
+        let bar = RecordFieldType::Note("bar".to_string());
+        assert_eq!(Some("bar".to_string()), iter.go_to_record(&[bar], None).map(|r| r.note));
+        assert_eq!(Some("two".to_string()), iter.section());
+        assert_eq!(Some(5), iter.cur_line_idx);
+
+        let mut iter = Iter::default().with_rope(rope.clone()).with_section(Some("one".to_string()));
+        assert_eq!(Some("bar".to_string()), iter.go_to_record(&[], Some(-1)).map(|r| r.note));
+        let mut iter = Iter::default().with_rope(rope.clone()).with_section(Some("two".to_string()));
+        assert_eq!(Some("bazz".to_string()), iter.go_to_record(&[], Some(-1)).map(|r| r.note));
+
+        let mut iter = Iter::default().with_rope(rope.clone());
+        assert!(iter.go_to_section_end("one"));
+        assert_eq!(Some(3), iter.cur_line_idx);
+        assert!(!iter.go_to_section_end("three"));
+
+        let mut iter = Iter::default().with_rope(rope).with_section(Some("three".to_string()));
+        assert!(iter.go_to_record(&[], None).is_none());
+    }
+
+    #[test]
+    fn iter_insert() {
+        let mut iter = Iter::default().with_rope(
+            Rope::from_reader(BufReader::new(Cursor::new(b"[,()] foo\n[,()] bar"))).unwrap(),
+        );
+        iter.insert(&item_line("first"));
+        assert_eq!(item_line("first"), iter.get().unwrap());
+        assert_eq!(item_record_with_note("foo"), iter.next().unwrap());
+        iter.insert(&item_record_with_note("bazz"));
+        assert_eq!(item_record_with_note("bar"), iter.next().unwrap());
+        iter.insert(&item_line("last"));
+        assert!(iter.next().is_none());
+        assert_eq!("first\n[,()] foo\n[, ] bazz\n[,()] bar\nlast\n", iter.rope.to_string());
+    }
+}
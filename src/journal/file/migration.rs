@@ -0,0 +1,129 @@
+use std::fmt;
+use crate::error::TimeTrackError;
+use crate::record::RECORD_REGEX;
+use crate::record::format::{RecordFormat, BracketFormat, DelimitedFormat};
+use super::header::Header;
+use super::history::{DiffLine, diff_lines};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceFormat {
+    /// Journal with a header declaring its format version.
+    Versioned(u32),
+    /// Bracket records without a header.
+    Bracket,
+    /// `2018-07-26 23:03 | 25m | 7m | note` records without a header.
+    Legacy,
+}
+
+impl fmt::Display for SourceFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SourceFormat::Versioned(version) => write!(f, "version {}", version),
+            SourceFormat::Bracket => f.write_str("bracket without header"),
+            SourceFormat::Legacy => f.write_str("legacy delimited"),
+        }
+    }
+}
+
+impl SourceFormat {
+    pub fn detect(content: &str) -> Result<Self, TimeTrackError> {
+        if let Some(header) = Header::parse(content)? {
+            return Ok(SourceFormat::Versioned(header.version));
+        }
+        let legacy = DelimitedFormat::legacy();
+        let bracket_count = content.lines().filter(|line| RECORD_REGEX.is_match(line)).count();
+        let legacy_count = content.lines().filter(|line| legacy.parse_record(line).is_ok()).count();
+        Ok(if legacy_count > bracket_count {
+            SourceFormat::Legacy
+        } else {
+            SourceFormat::Bracket
+        })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Migration {
+    pub from: SourceFormat,
+    pub header: Header,
+    pub lines: Vec<String>,
+    pub changes: Vec<DiffLine>,
+}
+
+impl Migration {
+    /// Plans the upgrade of the content to the current format without
+    /// touching any file.
+    pub fn plan(content: &str) -> Result<Self, TimeTrackError> {
+        let from = SourceFormat::detect(content)?;
+        let header = Header::parse(content)?.unwrap_or_default();
+        let body = content.lines().skip_while(|line| Header::is_header_line(line));
+        let lines: Vec<String> = match from {
+            SourceFormat::Versioned(version) if version > Header::CURRENT_VERSION => {
+                return Err(TimeTrackError::UnsupportedJournalVersion { version });
+            },
+            SourceFormat::Versioned(_) => body.map(str::to_string).collect(),
+            SourceFormat::Bracket => body.map(str::to_string).collect(),
+            SourceFormat::Legacy => {
+                let legacy = DelimitedFormat::legacy();
+                let bracket = BracketFormat::default();
+                body
+                    .map(|line| legacy.parse_record(line)
+                        .map(|record| bracket.format_record(&record))
+                        .unwrap_or_else(|_| line.to_string()))
+                    .collect()
+            },
+        };
+        let header = Header { version: Header::CURRENT_VERSION, ..header };
+
+        let mut migrated = header.to_string();
+        for line in &lines {
+            migrated += line;
+            migrated += "\n";
+        }
+        let changes = diff_lines(content, &migrated);
+        Ok(Migration { from, header, lines, changes })
+    }
+
+    /// Bracket journals that only lack a header are read as they are and
+    /// don't need a migration.
+    pub fn is_needed(&self) -> bool {
+        self.from != SourceFormat::Bracket && self.changes.iter().any(|line| !matches!(line, DiffLine::Same(_)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect() {
+        assert_eq!(Ok(SourceFormat::Versioned(3)), SourceFormat::detect("#! version: 3\n[, ] Note\n"));
+        assert_eq!(Ok(SourceFormat::Bracket), SourceFormat::detect("[, ] Note\n2018-07-26 23:03 | 25m | 7m | Note\n[, ] Note\n"));
+        assert_eq!(Ok(SourceFormat::Legacy), SourceFormat::detect("2018-07-26 23:03 | 25m | 7m | Note\n# comment\n"));
+        assert_eq!(Ok(SourceFormat::Bracket), SourceFormat::detect(""));
+    }
+
+    #[test]
+    fn plan() {
+        let migration = Migration::plan("2018-07-26 23:03 | 25m | 7m | Note 1\n# comment\n").unwrap();
+        assert_eq!(SourceFormat::Legacy, migration.from);
+        assert!(migration.is_needed());
+        assert_eq!(vec![
+            DiffLine::Removed("2018-07-26 23:03 | 25m | 7m | Note 1".to_string()),
+            DiffLine::Added("#! version: 1".to_string()),
+            DiffLine::Added("[2018-07-26 23:03:00, 25 (7)] Note 1".to_string()),
+            DiffLine::Same("# comment".to_string()),
+        ], migration.changes);
+
+        let migration = Migration::plan("#! version: 1\n[, ] Note\n").unwrap();
+        assert!(!migration.is_needed());
+
+        let migration = Migration::plan("[, ] Note\n").unwrap();
+        assert_eq!(SourceFormat::Bracket, migration.from);
+        assert!(!migration.is_needed());
+
+        assert_eq!(
+            Err(TimeTrackError::UnsupportedJournalVersion { version: 2 }),
+            Migration::plan("#! version: 2\n")
+        );
+    }
+}
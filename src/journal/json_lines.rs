@@ -0,0 +1,154 @@
+use std::ffi::{OsString, OsStr};
+use crate::error::TimeTrackError;
+use crate::record::{Record, RecordFieldType};
+use crate::record::format::RecordFormat;
+use crate::journal::{Journal, JournalResult};
+use crate::journal::file::{FileJournal, Iter, Item, history::History};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct JsonLinesFormat;
+
+impl JsonLinesFormat {
+    pub fn format_item(&self, item: &Item) -> String {
+        match item {
+            Item::Record(record) => self.format_record(record),
+            _ => serde_json::to_string(item).unwrap_or_default(),
+        }
+    }
+
+    pub fn parse_item(&self, line: &str) -> Item {
+        let line = line.trim();
+        serde_json::from_str::<Item>(line)
+            .or_else(|_| self.parse_record(line).map(Item::Record))
+            .unwrap_or_else(|_| Item::from_line(line))
+    }
+}
+
+impl RecordFormat for JsonLinesFormat {
+    fn parse_record(&self, line: &str) -> Result<Record, TimeTrackError> {
+        let line = line.trim();
+        match serde_json::from_str::<Item>(line) {
+            Ok(Item::Record(record)) => Ok(record),
+            Ok(_) => Err(TimeTrackError::CanNotParseRecord { source: line.to_string() }),
+            Err(_) => serde_json::from_str::<Record>(line)
+                .map_err(|_| TimeTrackError::CanNotParseRecord { source: line.to_string() }),
+        }
+    }
+
+    fn format_record(&self, record: &Record) -> String {
+        serde_json::to_string(record).unwrap_or_default()
+    }
+}
+
+pub struct JsonLinesJournal {
+    file: FileJournal,
+}
+
+impl JsonLinesJournal {
+    pub fn new<P: Into<OsString>>(path: P) -> Self {
+        JsonLinesJournal {
+            file: FileJournal::new(path).with_format(JsonLinesFormat),
+        }
+    }
+
+    pub fn with_history(mut self, history: History) -> Self {
+        self.file = self.file.with_history(history);
+        self
+    }
+
+    pub fn path(&self) -> &OsStr {
+        self.file.path()
+    }
+
+    pub fn file(&self) -> &FileJournal {
+        &self.file
+    }
+
+    pub fn try_iter(&self) -> JournalResult<Iter> {
+        self.file.try_iter()
+    }
+
+    pub fn import_text(&mut self, text: &FileJournal) -> JournalResult<usize> {
+        let mut count = 0;
+        let lines = text.try_iter()?
+            .map(|item| {
+                if let Item::Record(_) = item {
+                    count += 1;
+                }
+                JsonLinesFormat.format_item(&item)
+            })
+            .collect::<Vec<_>>();
+        self.file.replace_lines(lines)?;
+        Ok(count)
+    }
+
+    pub fn export_text(&self, text: &mut FileJournal) -> JournalResult<usize> {
+        let mut count = 0;
+        let lines = self.try_iter()?
+            .map(|item| match item {
+                Item::SomeLine(line) => JsonLinesFormat.parse_item(&line),
+                item => item,
+            })
+            .map(|item| match item {
+                Item::Record(record) => {
+                    count += 1;
+                    text.format().format_record(&record)
+                },
+                item => item.to_string(),
+            })
+            .collect::<Vec<_>>();
+        text.replace_lines(lines)?;
+        Ok(count)
+    }
+}
+
+impl Journal for JsonLinesJournal {
+    fn add(&mut self, record: &Record) -> JournalResult {
+        self.file.add(record)
+    }
+
+    fn get(&self, query: &[RecordFieldType], offset: Option<i32>) -> JournalResult<Option<Record>> {
+        self.file.get(query, offset)
+    }
+
+    fn records(&self) -> JournalResult<Vec<Record>> {
+        self.file.records()
+    }
+
+    fn update<F>(&mut self, query: &[RecordFieldType], offset: Option<i32>, f: F) -> JournalResult<bool>
+        where F: FnOnce(Record) -> Option<Record>,
+    {
+        self.file.update(query, offset, f)
+    }
+
+    fn remove<F>(&mut self, query: &[RecordFieldType], offset: Option<i32>, f: F) -> JournalResult<bool>
+        where F: FnOnce(Record) -> bool,
+    {
+        self.file.remove(query, offset, f)
+    }
+
+    fn remove_records(&mut self, positions: &[usize]) -> JournalResult<Vec<Record>> {
+        self.file.remove_records(positions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_lines_format() {
+        let format = JsonLinesFormat;
+        let record = format.parse_record(r#"{"start":null,"activity":25,"rest":-16,"note":"Some note"}"#).unwrap();
+        assert_eq!(Some(chrono::Duration::minutes(-16)), record.rest);
+        assert_eq!(r#"{"start":null,"activity":25,"rest":-16,"note":"Some note"}"#, format.format_record(&record));
+
+        assert!(format.parse_record(r#"{"some_line":"Some line"}"#).is_err());
+        assert!(format.parse_record("[, ] Some note").is_err());
+
+        let item = Item::SomeLine("Some line".to_string());
+        assert_eq!(r#"{"some_line":"Some line"}"#, format.format_item(&item));
+        assert_eq!(item, format.parse_item(r#"{"some_line":"Some line"}"#));
+        assert_eq!(Item::SomeLine("plain".to_string()), format.parse_item("plain"));
+    }
+}
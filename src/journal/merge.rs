@@ -0,0 +1,236 @@
+use std::collections::{HashMap, HashSet};
+use chrono::{DateTime, Local};
+use crate::record::Record;
+use crate::record::format::RecordFormat;
+
+/// Records are identified by start; the note is added to the key when the
+/// start is missing or shared by several records of any copy.
+pub type RecordKey = (Option<DateTime<Local>>, Option<String>);
+
+#[derive(Debug, PartialEq)]
+pub struct Conflict {
+    pub key: RecordKey,
+    pub base: Option<Record>,
+    pub ours: Option<Record>,
+    pub theirs: Option<Record>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct MergeResult {
+    /// Merged content, keeping our version of conflicting records.
+    pub lines: Vec<String>,
+    pub conflicts: Vec<Conflict>,
+}
+
+impl MergeResult {
+    pub fn is_clean(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+}
+
+enum Line {
+    Record(Record),
+    Other(String),
+}
+
+fn parse(content: &str, format: &dyn RecordFormat) -> Vec<Line> {
+    content.lines()
+        .map(|line| format.parse_record(line)
+            .map(Line::Record)
+            .unwrap_or_else(|_| Line::Other(line.to_string())))
+        .collect()
+}
+
+/// Starts that don't identify a record on their own in at least one copy.
+fn shared_starts(copies: &[&[Line]]) -> HashSet<Option<DateTime<Local>>> {
+    let mut shared = HashSet::new();
+    for lines in copies {
+        let mut starts = HashSet::new();
+        for line in lines.iter() {
+            if let Line::Record(record) = line {
+                if record.start.is_none() || !starts.insert(record.start) {
+                    shared.insert(record.start);
+                }
+            }
+        }
+    }
+    shared
+}
+
+fn keys(lines: &[Line], shared: &HashSet<Option<DateTime<Local>>>) -> Vec<Option<RecordKey>> {
+    lines.iter()
+        .map(|line| match line {
+            Line::Record(record) if shared.contains(&record.start) =>
+                Some((record.start, Some(record.note.clone()))),
+            Line::Record(record) => Some((record.start, None)),
+            Line::Other(_) => None,
+        })
+        .collect()
+}
+
+fn keyed(lines: Vec<Line>, shared: &HashSet<Option<DateTime<Local>>>) -> Vec<(RecordKey, Record)> {
+    keys(&lines, shared).into_iter()
+        .zip(lines)
+        .filter_map(|(key, line)| match line {
+            Line::Record(record) => Some((key?, record)),
+            Line::Other(_) => None,
+        })
+        .collect()
+}
+
+/// Returns the merged version of a record, or `None` on conflict.
+fn resolve<'a>(base: Option<&'a Record>, ours: Option<&'a Record>, theirs: Option<&'a Record>)
+    -> Option<Option<&'a Record>>
+{
+    if ours == theirs || theirs == base {
+        Some(ours)
+    } else if ours == base {
+        Some(theirs)
+    } else {
+        None
+    }
+}
+
+fn conflict(key: &RecordKey, base: Option<&Record>, ours: Option<&Record>, theirs: Option<&Record>) -> Conflict {
+    Conflict {
+        key: key.clone(),
+        base: base.cloned(),
+        ours: ours.cloned(),
+        theirs: theirs.cloned(),
+    }
+}
+
+/// Merges three copies of a journal record by record. Non-record lines are
+/// taken from our copy and records added only by them are appended.
+pub fn merge(base: &str, ours: &str, theirs: &str, format: &dyn RecordFormat) -> MergeResult {
+    let (base, ours, theirs) = (parse(base, format), parse(ours, format), parse(theirs, format));
+    let shared = shared_starts(&[&base, &ours, &theirs]);
+    let base = keyed(base, &shared).into_iter().collect::<HashMap<_, _>>();
+    let mut theirs = keyed(theirs, &shared).into_iter().map(Some).collect::<Vec<_>>();
+    let theirs_index = theirs.iter()
+        .enumerate()
+        .filter_map(|(idx, entry)| entry.as_ref().map(|(key, _)| (key.clone(), idx)))
+        .collect::<HashMap<_, _>>();
+
+    let mut result = MergeResult::default();
+    let ours_keys = keys(&ours, &shared);
+    for (line, key) in ours.into_iter().zip(ours_keys) {
+        let (record, key) = match (line, key) {
+            (Line::Record(record), Some(key)) => (record, key),
+            (Line::Record(record), None) => {
+                result.lines.push(format.format_record(&record));
+                continue;
+            },
+            (Line::Other(line), _) => {
+                result.lines.push(line);
+                continue;
+            },
+        };
+        let their_record = theirs_index.get(&key)
+            .and_then(|&idx| theirs[idx].take())
+            .map(|(_, record)| record);
+        match resolve(base.get(&key), Some(&record), their_record.as_ref()) {
+            Some(Some(merged)) => result.lines.push(format.format_record(merged)),
+            Some(None) => (),
+            None => {
+                result.lines.push(format.format_record(&record));
+                result.conflicts.push(conflict(&key, base.get(&key), Some(&record), their_record.as_ref()));
+            },
+        }
+    }
+
+    for (key, record) in theirs.into_iter().flatten() {
+        match resolve(base.get(&key), None, Some(&record)) {
+            Some(Some(merged)) => result.lines.push(format.format_record(merged)),
+            Some(None) => (),
+            None => result.conflicts.push(conflict(&key, base.get(&key), None, Some(&record))),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::record::format::BracketFormat;
+
+    const BASE: &str = "# Journal
+[2018-08-16 09:00:00, 30] Standup
+[2018-08-16 10:00:00, 60] Review
+[2018-08-16 12:00:00, 15] Email
+[2018-08-16 13:00:00, 20] Lunch
+";
+
+    #[test]
+    fn clean_merge() {
+        let ours = "# Journal
+[2018-08-16 09:00:00, 30 (5)] Standup
+[2018-08-16 10:00:00, 60] Review
+[2018-08-16 13:00:00, 20] Lunch
+[2018-08-16 14:00:00, 10] Ours
+";
+        let theirs = "# Journal
+[2018-08-16 09:00:00, 30] Standup
+[2018-08-16 10:00:00, 45] Review
+[2018-08-16 12:00:00, 15] Email
+[2018-08-16 13:00:00, 20] Lunch
+[2018-08-16 15:00:00, 10] Theirs
+";
+        let result = merge(BASE, ours, theirs, &BracketFormat::default());
+        assert!(result.is_clean());
+        assert_eq!(vec![
+            "# Journal",
+            "[2018-08-16 09:00:00, 30 (5)] Standup",
+            "[2018-08-16 10:00:00, 45] Review",
+            "[2018-08-16 13:00:00, 20] Lunch",
+            "[2018-08-16 14:00:00, 10] Ours",
+            "[2018-08-16 15:00:00, 10] Theirs",
+        ], result.lines);
+    }
+
+    #[test]
+    fn conflicts() {
+        let ours = "[2018-08-16 09:00:00, 35] Standup
+[2018-08-16 10:00:00, 60] Review
+[2018-08-16 12:00:00, 15] Email
+[2018-08-16 16:00:00, 10] Both
+";
+        let theirs = "[2018-08-16 09:00:00, 40] Standup
+[2018-08-16 10:00:00, 60] Review
+[2018-08-16 12:00:00, 20] Email
+[2018-08-16 13:00:00, 25] Lunch
+[2018-08-16 16:00:00, 10] Both
+";
+        let result = merge(BASE, ours, theirs, &BracketFormat::default());
+        assert_eq!(vec![
+            "[2018-08-16 09:00:00, 35] Standup",
+            "[2018-08-16 10:00:00, 60] Review",
+            "[2018-08-16 12:00:00, 20] Email",
+            "[2018-08-16 16:00:00, 10] Both",
+        ], result.lines);
+        assert_eq!(
+            vec![(Some(35), Some(40)), (None, Some(25))],
+            result.conflicts.iter()
+                .map(|c| (
+                    c.ours.as_ref().and_then(|r| r.activity).map(|a| a.num_minutes()),
+                    c.theirs.as_ref().and_then(|r| r.activity).map(|a| a.num_minutes()),
+                ))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(Some("Lunch".to_string()), result.conflicts[1].base.as_ref().map(|r| r.note.clone()));
+    }
+
+    #[test]
+    fn duplicate_starts() {
+        let base = "[, ] A\n[, ] B\n";
+        let result = merge(base, "[, ] A\n[, ] B\n[, ] C\n", "[, ] B\n", &BracketFormat::default());
+        assert!(result.is_clean());
+        assert_eq!(vec!["[, ] B", "[, ] C"], result.lines);
+
+        let ours = "[2018-08-16 09:00:00, 30] Standup\n[2018-08-16 09:00:00, 5] Sync\n";
+        let theirs = "[2018-08-16 09:00:00, 40] Standup\n";
+        let result = merge("[2018-08-16 09:00:00, 30] Standup\n", ours, theirs, &BracketFormat::default());
+        assert!(result.is_clean());
+        assert_eq!(vec!["[2018-08-16 09:00:00, 40] Standup", "[2018-08-16 09:00:00, 5] Sync"], result.lines);
+    }
+}
@@ -0,0 +1,132 @@
+use crate::record::{Record, RecordFieldType};
+use crate::journal::{Journal, JournalResult, matches_query, offset_index, record_query};
+use crate::convert::note_word;
+
+#[derive(Debug, PartialEq)]
+pub struct LabeledRecord {
+    pub source: String,
+    pub record: Record,
+}
+
+impl LabeledRecord {
+    /// Returns the record with its source appended as a tag, so reports and
+    /// filters can tell the sources apart.
+    pub fn tagged(self) -> Record {
+        let (tag, _) = note_word(Record::TAG_PREFIX, &self.source);
+        let record = self.record;
+        if record.has_tag(&tag[1..]) {
+            return record;
+        }
+        let note = if record.note.is_empty() { tag } else { format!("{} {}", record.note, tag) };
+        Record { note, ..record }
+    }
+}
+
+/// Merged view over several journals ordered by record start. Queries and
+/// offsets are resolved over the merged view; writes go to the primary
+/// journal only, so records from other journals are left untouched.
+pub struct MultiJournal<J> {
+    journals: Vec<(String, J)>,
+    primary: usize,
+}
+
+impl<J: Journal> MultiJournal<J> {
+    pub fn new<S: Into<String>>(label: S, primary: J) -> Self {
+        MultiJournal {
+            journals: vec![(label.into(), primary)],
+            primary: 0,
+        }
+    }
+
+    pub fn with_journal<S: Into<String>>(mut self, label: S, journal: J) -> Self {
+        self.journals.push((label.into(), journal));
+        self
+    }
+
+    /// Makes the journal with the given label the primary one.
+    pub fn with_primary(mut self, label: &str) -> Self {
+        if let Some(idx) = self.journals.iter().position(|(source, _)| source == label) {
+            self.primary = idx;
+        }
+        self
+    }
+
+    pub fn labels(&self) -> impl Iterator<Item = &str> {
+        self.journals.iter().map(|(label, _)| label.as_str())
+    }
+
+    pub fn journal(&self, label: &str) -> Option<&J> {
+        self.journals.iter().find(|(source, _)| source == label).map(|(_, journal)| journal)
+    }
+
+    pub fn primary_label(&self) -> &str {
+        &self.journals[self.primary].0
+    }
+
+    pub fn primary(&self) -> &J {
+        &self.journals[self.primary].1
+    }
+
+    pub fn primary_mut(&mut self) -> &mut J {
+        &mut self.journals[self.primary].1
+    }
+
+    pub fn labeled_records(&self) -> JournalResult<Vec<LabeledRecord>> {
+        let mut records = Vec::new();
+        for (label, journal) in &self.journals {
+            records.extend(journal.records()?
+                .into_iter()
+                .map(|record| LabeledRecord { source: label.clone(), record }));
+        }
+        records.sort_by_key(|labeled| labeled.record.start);
+        Ok(records)
+    }
+
+    pub fn get_labeled(&self, query: &[RecordFieldType], offset: Option<i32>) -> JournalResult<Option<LabeledRecord>> {
+        let mut records = self.labeled_records()?;
+        Ok(records.iter()
+            .position(|labeled| matches_query(&labeled.record, query))
+            .and_then(|idx| offset_index(records.len(), idx, offset))
+            .map(|idx| records.swap_remove(idx)))
+    }
+
+    /// Resolves the query over the merged view and returns the record only if
+    /// it belongs to the primary journal.
+    fn get_primary(&self, query: &[RecordFieldType], offset: Option<i32>) -> JournalResult<Option<Record>> {
+        Ok(self.get_labeled(query, offset)?
+            .filter(|labeled| labeled.source == self.primary_label())
+            .map(|labeled| labeled.record))
+    }
+}
+
+impl<J: Journal> Journal for MultiJournal<J> {
+    fn add(&mut self, record: &Record) -> JournalResult {
+        self.primary_mut().add(record)
+    }
+
+    fn get(&self, query: &[RecordFieldType], offset: Option<i32>) -> JournalResult<Option<Record>> {
+        Ok(self.get_labeled(query, offset)?.map(|labeled| labeled.record))
+    }
+
+    fn records(&self) -> JournalResult<Vec<Record>> {
+        Ok(self.labeled_records()?.into_iter().map(|labeled| labeled.record).collect())
+    }
+
+    fn update<F>(&mut self, query: &[RecordFieldType], offset: Option<i32>, f: F) -> JournalResult<bool>
+        where F: FnOnce(Record) -> Option<Record>,
+    {
+        match self.get_primary(query, offset)? {
+            Some(record) => self.primary_mut().update(&record_query(&record), None, f),
+            None => Ok(false),
+        }
+    }
+
+    fn remove<F>(&mut self, query: &[RecordFieldType], offset: Option<i32>, f: F) -> JournalResult<bool>
+        where F: FnOnce(Record) -> bool,
+    {
+        match self.get_primary(query, offset)? {
+            Some(record) => self.primary_mut().remove(&record_query(&record), None, f),
+            None => Ok(false),
+        }
+    }
+}
@@ -0,0 +1,171 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use chrono::Datelike;
+use crate::record::{Record, RecordFieldType};
+use crate::journal::{Journal, JournalResult, matches_query, offset_index, record_query};
+use crate::journal::file::FileJournal;
+
+/// Journal stored as `YYYY/MM.txt` files under a root directory. Records
+/// without a start go to `undated.txt` which precedes all monthly shards.
+pub struct ShardedJournal {
+    root: PathBuf,
+}
+
+impl ShardedJournal {
+    pub const UNDATED: &'static str = "undated.txt";
+
+    pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+        ShardedJournal { root: root.into() }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn shard_path(&self, record: &Record) -> PathBuf {
+        match record.start {
+            Some(start) => self.root
+                .join(format!("{:04}", start.year()))
+                .join(format!("{:02}.txt", start.month())),
+            None => self.root.join(ShardedJournal::UNDATED),
+        }
+    }
+
+    fn is_number(name: &str, len: usize) -> bool {
+        name.len() == len && name.chars().all(|c| c.is_ascii_digit())
+    }
+
+    /// Returns the existing shard files in chronological order.
+    pub fn shards(&self) -> JournalResult<Vec<PathBuf>> {
+        let mut shards = Vec::new();
+        let undated = self.root.join(ShardedJournal::UNDATED);
+        if undated.is_file() {
+            shards.push(undated);
+        }
+        if !self.root.is_dir() {
+            return Ok(shards);
+        }
+
+        let mut months = Vec::new();
+        for year in fs::read_dir(&self.root)? {
+            let year = year?.path();
+            let is_year = year.file_name()
+                .and_then(|name| name.to_str())
+                .map(|name| ShardedJournal::is_number(name, 4))
+                .unwrap_or(false);
+            if !is_year || !year.is_dir() {
+                continue;
+            }
+            for month in fs::read_dir(&year)? {
+                let month = month?.path();
+                let is_month = month.extension().map(|ext| ext == "txt").unwrap_or(false)
+                    && month.file_stem()
+                        .and_then(|stem| stem.to_str())
+                        .map(|stem| ShardedJournal::is_number(stem, 2))
+                        .unwrap_or(false);
+                if is_month && month.is_file() {
+                    months.push(month);
+                }
+            }
+        }
+        months.sort();
+        shards.extend(months);
+        Ok(shards)
+    }
+
+    pub fn shard(&self, path: &Path) -> FileJournal {
+        FileJournal::new(path)
+    }
+
+    pub fn shard_records(&self) -> JournalResult<Vec<(PathBuf, Record)>> {
+        let mut records = Vec::new();
+        for shard in self.shards()? {
+            let journal = self.shard(&shard);
+            records.extend(journal.try_iter()?
+                .filter_map(|item| item.into_record())
+                .map(|record| (shard.clone(), record)));
+        }
+        Ok(records)
+    }
+
+    /// Finds a record like `FileJournal` does, except that the offset counts
+    /// records rather than lines, across shard boundaries.
+    fn locate(&self, query: &[RecordFieldType], offset: Option<i32>) -> JournalResult<Option<(PathBuf, Record)>> {
+        let mut records = self.shard_records()?;
+        Ok(records.iter()
+            .position(|(_, record)| matches_query(record, query))
+            .and_then(|idx| offset_index(records.len(), idx, offset))
+            .map(|idx| records.swap_remove(idx)))
+    }
+}
+
+impl Journal for ShardedJournal {
+    fn add(&mut self, record: &Record) -> JournalResult {
+        let path = self.shard_path(record);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        self.shard(&path).add(record)
+    }
+
+    fn get(&self, query: &[RecordFieldType], offset: Option<i32>) -> JournalResult<Option<Record>> {
+        Ok(self.locate(query, offset)?.map(|(_, record)| record))
+    }
+
+    fn records(&self) -> JournalResult<Vec<Record>> {
+        Ok(self.shard_records()?.into_iter().map(|(_, record)| record).collect())
+    }
+
+    fn update<F>(&mut self, query: &[RecordFieldType], offset: Option<i32>, f: F) -> JournalResult<bool>
+        where F: FnOnce(Record) -> Option<Record>,
+    {
+        let (path, record) = match self.locate(query, offset)? {
+            Some(found) => found,
+            None => return Ok(false),
+        };
+        let fields = record_query(&record);
+        let new_record = match f(record) {
+            Some(new_record) => new_record,
+            None => return Ok(false),
+        };
+        if self.shard_path(&new_record) == path {
+            return self.shard(&path).update(&fields, None, |_| Some(new_record));
+        }
+
+        // The record moves to another shard when its start changes month.
+        if !self.shard(&path).remove(&fields, None, |_| true)? {
+            return Ok(false);
+        }
+        self.add(&new_record)?;
+        Ok(true)
+    }
+
+    fn remove<F>(&mut self, query: &[RecordFieldType], offset: Option<i32>, f: F) -> JournalResult<bool>
+        where F: FnOnce(Record) -> bool,
+    {
+        match self.locate(query, offset)? {
+            Some((path, record)) => self.shard(&path).remove(&record_query(&record), None, f),
+            None => Ok(false),
+        }
+    }
+
+    fn remove_records(&mut self, positions: &[usize]) -> JournalResult<Vec<Record>> {
+        let mut shards: Vec<(PathBuf, usize, Vec<usize>)> = Vec::new();
+        for (idx, (path, _)) in self.shard_records()?.into_iter().enumerate() {
+            match shards.last_mut() {
+                Some((last, count, _)) if *last == path => *count += 1,
+                _ => shards.push((path, 1, Vec::new())),
+            }
+            if let Some((_, count, local)) = shards.last_mut() {
+                if positions.contains(&idx) {
+                    local.push(*count - 1);
+                }
+            }
+        }
+        let mut removed = Vec::new();
+        for (path, _, local) in shards.into_iter().filter(|(_, _, local)| !local.is_empty()) {
+            removed.extend(self.shard(&path).remove_records(&local)?);
+        }
+        Ok(removed)
+    }
+}
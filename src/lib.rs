@@ -1,6 +1,14 @@
 pub mod journal;
 pub mod error;
 pub mod record;
+pub mod convert;
+pub mod report;
+pub mod billing;
+pub mod rounding;
+pub mod analysis;
+pub mod idle;
+pub mod pomodoro;
+pub mod budget;
 
 pub use ropey;
 pub use chrono;
\ No newline at end of file
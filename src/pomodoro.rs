@@ -0,0 +1,260 @@
+use chrono::{DateTime, Duration, Local, Timelike};
+use crate::error::TimeTrackError;
+use crate::record::Record;
+use crate::journal::{Journal, JournalResult, record_query};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cycle {
+    work: Duration,
+    short_break: Duration,
+    long_break: Duration,
+    long_break_every: usize,
+}
+
+impl Default for Cycle {
+    fn default() -> Self {
+        Cycle {
+            work: Duration::minutes(25),
+            short_break: Duration::minutes(5),
+            long_break: Duration::minutes(15),
+            long_break_every: 4,
+        }
+    }
+}
+
+impl Cycle {
+    pub fn new(work: Duration, short_break: Duration) -> Result<Self, TimeTrackError> {
+        positive("work", work)?;
+        positive("short break", short_break)?;
+        Ok(Cycle { work, short_break, ..Cycle::default() })
+    }
+
+    pub fn with_long_break(mut self, long_break: Duration, every: usize) -> Result<Self, TimeTrackError> {
+        positive("long break", long_break)?;
+        self.long_break = long_break;
+        self.long_break_every = every;
+        Ok(self)
+    }
+
+    /// Work followed by the longest break.
+    pub fn span(&self) -> Duration {
+        self.work + self.short_break.max(self.long_break)
+    }
+
+    pub fn duration(&self, phase: Phase) -> Duration {
+        match phase {
+            Phase::Work => self.work,
+            Phase::ShortBreak => self.short_break,
+            Phase::LongBreak => self.long_break,
+        }
+    }
+
+    /// The break following the given number of completed pomodoros.
+    pub fn break_after(&self, completed: usize) -> Phase {
+        if completed.checked_rem(self.long_break_every) == Some(0) {
+            Phase::LongBreak
+        } else {
+            Phase::ShortBreak
+        }
+    }
+}
+
+fn positive(name: &str, duration: Duration) -> Result<(), TimeTrackError> {
+    if duration > Duration::zero() {
+        Ok(())
+    } else {
+        Err(TimeTrackError::NonPositiveDuration { name: name.to_string() })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Work,
+    ShortBreak,
+    LongBreak,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    Started {
+        at: DateTime<Local>,
+    },
+    PhaseChanged {
+        from: Phase,
+        to: Phase,
+        at: DateTime<Local>,
+        completed: usize,
+    },
+    Stopped {
+        at: DateTime<Local>,
+        completed: usize,
+    },
+}
+
+struct Running {
+    record: Record,
+    phase: Phase,
+    phase_start: DateTime<Local>,
+    synced_at: DateTime<Local>,
+}
+
+/// Keeps one record per pomodoro in the journal: the work goes to its
+/// activity and the following break to its rest.
+pub struct Pomodoro {
+    cycle: Cycle,
+    note: String,
+    completed: usize,
+    running: Option<Running>,
+}
+
+impl Pomodoro {
+    pub fn new<S: Into<String>>(note: S) -> Self {
+        Pomodoro {
+            cycle: Cycle::default(),
+            note: note.into(),
+            completed: 0,
+            running: None,
+        }
+    }
+
+    pub fn with_cycle(mut self, cycle: Cycle) -> Self {
+        self.cycle = cycle;
+        self
+    }
+
+    pub fn cycle(&self) -> &Cycle {
+        &self.cycle
+    }
+
+    pub fn completed(&self) -> usize {
+        self.completed
+    }
+
+    pub fn phase(&self) -> Option<Phase> {
+        self.running.as_ref().map(|running| running.phase)
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.is_some()
+    }
+
+    /// Time left in the current phase.
+    pub fn remaining(&self, now: DateTime<Local>) -> Option<Duration> {
+        self.running.as_ref()
+            .map(|running| running.phase_start + self.cycle.duration(running.phase) - now)
+    }
+
+    pub fn start<J: Journal>(&mut self, journal: &mut J, now: DateTime<Local>) -> JournalResult<Event> {
+        if self.running.is_some() {
+            self.stop(journal, now)?;
+        }
+        self.completed = 0;
+        self.running = Some(self.begin_work(journal, now)?);
+        Ok(Event::Started { at: now })
+    }
+
+    /// Brings the running record up to `now` and returns the phase changes passed on the way.
+    /// A gap since the last tick longer than a cycle stops the session where it was left.
+    pub fn tick<J: Journal>(&mut self, journal: &mut J, now: DateTime<Local>) -> JournalResult<Vec<Event>> {
+        let mut events = Vec::new();
+        let mut running = match self.running.take() {
+            Some(running) => running,
+            None => return Ok(events),
+        };
+        if now - running.synced_at > self.cycle.span() {
+            events.push(Event::Stopped { at: running.synced_at, completed: self.completed });
+            return Ok(events);
+        }
+        loop {
+            let phase_end = running.phase_start + self.cycle.duration(running.phase);
+            if now < phase_end {
+                break;
+            }
+            let from = running.phase;
+            match from {
+                Phase::Work => {
+                    self.completed += 1;
+                    running.phase = self.cycle.break_after(self.completed);
+                    running.phase_start = phase_end;
+                },
+                Phase::ShortBreak | Phase::LongBreak => {
+                    self.sync(journal, &mut running, phase_end)?;
+                    running = self.begin_work(journal, phase_end)?;
+                },
+            }
+            events.push(Event::PhaseChanged { from, to: running.phase, at: phase_end, completed: self.completed });
+        }
+        self.sync(journal, &mut running, now)?;
+        self.running = Some(running);
+        Ok(events)
+    }
+
+    pub fn stop<J: Journal>(&mut self, journal: &mut J, now: DateTime<Local>) -> JournalResult<Option<Event>> {
+        let stopped = self.tick(journal, now)?.into_iter().find(|event| matches!(event, Event::Stopped { .. }));
+        Ok(stopped.or_else(|| self.running.take().map(|_| Event::Stopped { at: now, completed: self.completed })))
+    }
+
+    fn begin_work<J: Journal>(&self, journal: &mut J, at: DateTime<Local>) -> JournalResult<Running> {
+        let record = Record {
+            start: at.with_nanosecond(0),
+            activity: Some(Duration::zero()),
+            rest: None,
+            note: self.note.clone(),
+        };
+        journal.add(&record)?;
+        Ok(Running { record, phase: Phase::Work, phase_start: at, synced_at: at })
+    }
+
+    fn sync<J: Journal>(&self, journal: &mut J, running: &mut Running, at: DateTime<Local>) -> JournalResult<()> {
+        let start = running.record.start.unwrap_or(at);
+        // The journal keeps whole minutes, so the record is kept as it's stored.
+        let elapsed = (at - start).max(Duration::zero());
+        let activity = Duration::minutes(elapsed.min(self.cycle.work).num_minutes());
+        let rest = Duration::minutes((elapsed - self.cycle.work).num_minutes().max(0));
+        let record = Record {
+            activity: Some(activity),
+            rest: if rest > Duration::zero() { Some(rest) } else { None },
+            ..running.record.clone()
+        };
+        if record != running.record {
+            if !journal.update(&record_query(&running.record), None, |_| Some(record.clone()))? {
+                return Err(TimeTrackError::RecordNotFound { source: running.record.to_string() }.into());
+            }
+            running.record = record;
+        }
+        running.synced_at = at;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn break_after() {
+        let cycle = Cycle::default();
+        assert_eq!(Phase::ShortBreak, cycle.break_after(1));
+        assert_eq!(Phase::ShortBreak, cycle.break_after(3));
+        assert_eq!(Phase::LongBreak, cycle.break_after(4));
+        assert_eq!(Phase::LongBreak, cycle.break_after(8));
+        assert_eq!(Phase::ShortBreak, cycle.with_long_break(Duration::minutes(15), 0).unwrap().break_after(4));
+        assert_eq!(Duration::minutes(15), cycle.duration(Phase::LongBreak));
+    }
+
+    #[test]
+    fn non_positive_durations() {
+        assert_eq!(
+            Err(TimeTrackError::NonPositiveDuration { name: "work".to_string() }),
+            Cycle::new(Duration::zero(), Duration::minutes(5))
+        );
+        assert_eq!(
+            Err(TimeTrackError::NonPositiveDuration { name: "short break".to_string() }),
+            Cycle::new(Duration::minutes(25), Duration::minutes(-5))
+        );
+        assert_eq!(
+            Err(TimeTrackError::NonPositiveDuration { name: "long break".to_string() }),
+            Cycle::default().with_long_break(Duration::zero(), 4)
+        );
+    }
+}
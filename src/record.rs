@@ -1,3 +1,7 @@
+pub mod format;
+#[cfg(feature = "serde")]
+pub mod serialization;
+
 use std::string::ToString;
 use std::str::FromStr;
 use regex::Regex;
@@ -5,6 +9,7 @@ pub use chrono::{DateTime, Local, Duration, TimeZone, Timelike, Date, Datelike};
 use lazy_static::lazy_static;
 use field_types::{FieldType, FieldName};
 use crate::error::TimeTrackError;
+use self::format::{RecordFormat, BracketFormat};
 
 lazy_static! {
     pub static ref RECORD_REGEX: Regex = {
@@ -19,16 +24,23 @@ lazy_static! {
     };
 }
 
-#[derive(Debug, Default, PartialEq, PartialOrd, FieldType, FieldName)]
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd, FieldType, FieldName)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Record {
+    #[cfg_attr(feature = "serde", serde(with = "serialization::start", default))]
     pub start: Option<DateTime<Local>>,
+    #[cfg_attr(feature = "serde", serde(with = "serialization::minutes", default))]
     pub activity: Option<Duration>,
+    #[cfg_attr(feature = "serde", serde(with = "serialization::minutes", default))]
     pub rest: Option<Duration>,
+    #[cfg_attr(feature = "serde", serde(default))]
     pub note: String,
 }
 
 impl Record {
     pub const START_DATETIME_FORMAT: &'static str = "%Y-%m-%d %H:%M:%S";
+    pub const TAG_PREFIX: char = '#';
+    pub const PROJECT_PREFIX: char = '@';
 
     pub fn now() -> Self {
         let now = Local::now();
@@ -57,38 +69,78 @@ impl Record {
             self.rest = Some(self.duration_until_now() - self.activity.unwrap_or(Duration::minutes(0)));
         }
     }
-}
 
-impl ToString for Record {
-    fn to_string(&self) -> String {
-        let rest = if let Some(rest) = self.rest {
-            format!(" ({})", rest.num_minutes())
-        } else {
-            String::new()
-        };
+    pub fn end(&self) -> Option<DateTime<Local>> {
+        self.start.map(|start|
+            start + self.activity.unwrap_or(Duration::minutes(0)) + self.rest.unwrap_or(Duration::minutes(0))
+        )
+    }
 
-        let timing = if let Some(activity) = self.activity {
-            format!("{}{}", activity.num_minutes(), rest)
-        } else {
-            rest
-        };
+    /// Splits the record at the time into two records with the same note.
+    /// A split inside the rest gives the second record no activity.
+    pub fn split_at(&self, time: DateTime<Local>) -> Option<(Record, Record)> {
+        let start = self.start?;
+        let activity = self.activity?;
+        let activity_end = start + activity;
+        let end = self.end()?;
+        if time <= start || time >= end {
+            return None;
+        }
 
-        let line = format!(
-            "[{}, {}]",
-            self.start
-                .map(|dt|
-                    dt.format(Record::START_DATETIME_FORMAT).to_string()
-                )
-                .as_ref()
-                .map(String::as_str)
-                .unwrap_or(""),
-            timing
-        );
-        if !self.note.is_empty() {
-            format!("{} {}", line, self.note)
+        Some(if time <= activity_end {
+            (
+                Record { activity: Some(time - start), rest: None, ..self.clone() },
+                Record { start: Some(time), activity: Some(activity_end - time), ..self.clone() },
+            )
         } else {
-            line
+            (
+                Record { rest: Some(time - activity_end), ..self.clone() },
+                Record { start: Some(time), activity: Some(Duration::zero()), rest: Some(end - time), note: self.note.clone() },
+            )
+        })
+    }
+
+    /// Joins the record with the following one having the same note. The time
+    /// between them is counted as rest.
+    pub fn merge(&self, next: &Record) -> Option<Record> {
+        let start = self.start?;
+        let next_end = next.end()?;
+        if self.note != next.note || next.start? < start {
+            return None;
         }
+
+        let activity = match (self.activity, next.activity) {
+            (None, None) => None,
+            (first, second) => Some(first.unwrap_or_else(Duration::zero) + second.unwrap_or_else(Duration::zero)),
+        };
+        let rest = next_end - start - activity.unwrap_or_else(Duration::zero);
+        let rest = if rest == Duration::zero() && self.rest.is_none() && next.rest.is_none() { None } else { Some(rest) };
+        Some(Record { start: Some(start), activity, rest, note: self.note.clone() })
+    }
+
+    pub fn tags(&self) -> Vec<&str> {
+        self.note
+            .split_whitespace()
+            .filter(|word| word.len() > 1 && word.starts_with(Record::TAG_PREFIX))
+            .map(|word| &word[Record::TAG_PREFIX.len_utf8()..])
+            .collect()
+    }
+
+    pub fn project(&self) -> Option<&str> {
+        self.note
+            .split_whitespace()
+            .find(|word| word.len() > 1 && word.starts_with(Record::PROJECT_PREFIX))
+            .map(|word| &word[Record::PROJECT_PREFIX.len_utf8()..])
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags().contains(&tag)
+    }
+}
+
+impl ToString for Record {
+    fn to_string(&self) -> String {
+        BracketFormat::default().format_record(self)
     }
 }
 
@@ -96,23 +148,24 @@ impl FromStr for Record {
     type Err = TimeTrackError;
 
     fn from_str(source: &str) -> Result<Self, Self::Err> {
-        if let Some(caps) = RECORD_REGEX.captures_iter(source).next() {
-            Ok(Record {
-                start: Local.datetime_from_str(&caps[RecordFieldName::Start.name()],
-                                               Record::START_DATETIME_FORMAT).ok(),
-                activity: caps[RecordFieldName::Activity.name()].parse::<i64>()
-                    .map(|min| Duration::minutes(min)).ok(),
-                rest: caps.name(RecordFieldName::Rest.name())
-                    .and_then(|rest| rest.as_str().parse::<i64>().ok())
-                    .map(|min| Duration::minutes(min)),
-                note: caps[RecordFieldName::Note.name()].to_string(),
-            })
-        } else {
-            Err(TimeTrackError::CanNotParseRecord { source: source.to_string() })
-        }
+        BracketFormat::default().parse_record(source)
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecordMatcher {
+    Tag(String),
+    Project(String),
+}
+
+impl RecordMatcher {
+    pub fn matches(&self, record: &Record) -> bool {
+        match self {
+            RecordMatcher::Tag(tag) => record.has_tag(tag),
+            RecordMatcher::Project(project) => record.project() == Some(project.as_str()),
+        }
+    }
+}
 
 pub enum RecordQuery {
     Field(RecordFieldType),
@@ -228,6 +281,66 @@ mod tests {
         assert_eq!(record.activity.unwrap(), Duration::minutes(30));
     }
 
+    #[test]
+    fn end_tags_project() {
+        let record = Record {
+            start: Local.timestamp_opt(1_534_416_763, 0).single(),
+            activity: Some(Duration::minutes(42)),
+            rest: Some(Duration::minutes(-5)),
+            note: "Review @tt-core #work #review # issue#5".to_string(),
+        };
+        assert_eq!(record.start.map(|start| start + Duration::minutes(37)), record.end());
+        assert_eq!(vec!["work", "review"], record.tags());
+        assert!(record.has_tag("work"));
+        assert!(!record.has_tag("issue"));
+        assert_eq!(Some("tt-core"), record.project());
+
+        let record = Record::default();
+        assert_eq!(None, record.end());
+        assert!(record.tags().is_empty());
+        assert_eq!(None, record.project());
+    }
+
+    #[test]
+    fn split_merge() {
+        let start = Local.timestamp_opt(1_534_416_763, 0).unwrap();
+        let record = Record {
+            start: Some(start),
+            activity: Some(Duration::minutes(60)),
+            rest: Some(Duration::minutes(10)),
+            note: "Review".to_string(),
+        };
+
+        let (first, second) = record.split_at(start + Duration::minutes(20)).unwrap();
+        assert_eq!((Some(Duration::minutes(20)), None), (first.activity, first.rest));
+        assert_eq!(Some(start + Duration::minutes(20)), second.start);
+        assert_eq!((Some(Duration::minutes(40)), Some(Duration::minutes(10))), (second.activity, second.rest));
+        assert_eq!("Review", second.note);
+        assert_eq!(Some(record.clone()), first.merge(&second));
+
+        let (first, second) = record.split_at(start + Duration::minutes(65)).unwrap();
+        assert_eq!((Some(Duration::minutes(60)), Some(Duration::minutes(5))), (first.activity, first.rest));
+        assert_eq!((Some(Duration::zero()), Some(Duration::minutes(5))), (second.activity, second.rest));
+        assert_eq!(record.end(), second.end());
+
+        assert_eq!(None, record.split_at(start));
+        assert_eq!(None, record.split_at(start + Duration::minutes(70)));
+        assert_eq!(None, Record::default().split_at(start));
+
+        let next = Record {
+            start: Some(start + Duration::minutes(90)),
+            activity: Some(Duration::minutes(15)),
+            rest: None,
+            note: "Review".to_string(),
+        };
+        let merged = record.merge(&next).unwrap();
+        assert_eq!(Some(Duration::minutes(75)), merged.activity);
+        assert_eq!(Some(Duration::minutes(30)), merged.rest);
+        assert_eq!(next.end(), merged.end());
+        assert_eq!(None, next.merge(&record));
+        assert_eq!(None, record.merge(&Record { note: "Other".to_string(), ..next }));
+    }
+
     #[test]
     fn set_rest_to_now() {
         let mut record = Record {
@@ -0,0 +1,252 @@
+use chrono::{DateTime, Local, Duration, NaiveDateTime, TimeZone};
+use crate::error::TimeTrackError;
+use crate::record::{Record, RecordFieldName, RECORD_REGEX};
+
+pub trait RecordFormat: Send + Sync {
+    fn parse_record(&self, line: &str) -> Result<Record, TimeTrackError>;
+    fn format_record(&self, record: &Record) -> String;
+}
+
+fn parse_datetime(source: &str, format: &str) -> Option<DateTime<Local>> {
+    NaiveDateTime::parse_from_str(source, format).ok()
+        .and_then(|naive| Local.from_local_datetime(&naive).earliest())
+}
+
+fn format_datetime(datetime: Option<DateTime<Local>>, format: &str) -> String {
+    datetime
+        .map(|dt| dt.format(format).to_string())
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BracketFormat {
+    datetime_format: String,
+}
+
+impl BracketFormat {
+    pub fn with_datetime_format<S: Into<String>>(mut self, format: S) -> Self {
+        self.datetime_format = format.into();
+        self
+    }
+
+    pub fn datetime_format(&self) -> &str {
+        &self.datetime_format
+    }
+}
+
+impl Default for BracketFormat {
+    fn default() -> Self {
+        BracketFormat {
+            datetime_format: Record::START_DATETIME_FORMAT.to_string(),
+        }
+    }
+}
+
+impl RecordFormat for BracketFormat {
+    fn parse_record(&self, line: &str) -> Result<Record, TimeTrackError> {
+        if let Some(caps) = RECORD_REGEX.captures_iter(line).next() {
+            Ok(Record {
+                start: parse_datetime(&caps[RecordFieldName::Start.name()], &self.datetime_format),
+                activity: caps[RecordFieldName::Activity.name()].parse::<i64>()
+                    .map(Duration::minutes).ok(),
+                rest: caps.name(RecordFieldName::Rest.name())
+                    .and_then(|rest| rest.as_str().parse::<i64>().ok())
+                    .map(Duration::minutes),
+                note: caps[RecordFieldName::Note.name()].to_string(),
+            })
+        } else {
+            Err(TimeTrackError::CanNotParseRecord { source: line.to_string() })
+        }
+    }
+
+    fn format_record(&self, record: &Record) -> String {
+        let rest = if let Some(rest) = record.rest {
+            format!(" ({})", rest.num_minutes())
+        } else {
+            String::new()
+        };
+
+        let timing = if let Some(activity) = record.activity {
+            format!("{}{}", activity.num_minutes(), rest)
+        } else {
+            rest
+        };
+
+        let line = format!("[{}, {}]", format_datetime(record.start, &self.datetime_format), timing);
+        if !record.note.is_empty() {
+            format!("{} {}", line, record.note)
+        } else {
+            line
+        }
+    }
+}
+
+pub struct DelimitedFormat {
+    datetime_format: String,
+    delimiter: String,
+    duration_suffix: String,
+    fields: Vec<RecordFieldName>,
+}
+
+impl DelimitedFormat {
+    pub fn new<S: Into<String>>(delimiter: S, fields: Vec<RecordFieldName>) -> Self {
+        DelimitedFormat {
+            datetime_format: Record::START_DATETIME_FORMAT.to_string(),
+            delimiter: delimiter.into(),
+            duration_suffix: String::new(),
+            fields,
+        }
+    }
+
+    pub fn legacy() -> Self {
+        DelimitedFormat::new(" | ", vec![
+            RecordFieldName::Start,
+            RecordFieldName::Activity,
+            RecordFieldName::Rest,
+            RecordFieldName::Note,
+        ])
+            .with_datetime_format("%Y-%m-%d %H:%M")
+            .with_duration_suffix("m")
+    }
+
+    pub fn with_datetime_format<S: Into<String>>(mut self, format: S) -> Self {
+        self.datetime_format = format.into();
+        self
+    }
+
+    pub fn with_duration_suffix<S: Into<String>>(mut self, suffix: S) -> Self {
+        self.duration_suffix = suffix.into();
+        self
+    }
+
+    fn parse_duration(&self, source: &str) -> Result<Option<Duration>, ()> {
+        let source = source.trim();
+        if source.is_empty() {
+            return Ok(None);
+        }
+        let source = if self.duration_suffix.is_empty() {
+            source
+        } else {
+            source.trim_end_matches(self.duration_suffix.as_str()).trim_end()
+        };
+        source.parse::<i64>().map(|min| Some(Duration::minutes(min))).map_err(|_| ())
+    }
+
+    fn format_duration(&self, duration: Option<Duration>) -> String {
+        duration
+            .map(|duration| format!("{}{}", duration.num_minutes(), self.duration_suffix))
+            .unwrap_or_default()
+    }
+}
+
+impl RecordFormat for DelimitedFormat {
+    fn parse_record(&self, line: &str) -> Result<Record, TimeTrackError> {
+        let error = || TimeTrackError::CanNotParseRecord { source: line.to_string() };
+        let trimmed = line.trim_end_matches(&['\r', '\n'][..]);
+        let split_by = match self.delimiter.trim() {
+            "" => self.delimiter.as_str(),
+            delimiter => delimiter,
+        };
+
+        let values = trimmed.splitn(self.fields.len(), split_by).collect::<Vec<_>>();
+        if values.len() != self.fields.len() {
+            return Err(error());
+        }
+
+        let mut record = Record::default();
+        for (field, value) in self.fields.iter().zip(values) {
+            let value = value.trim();
+            match field {
+                RecordFieldName::Start => if !value.is_empty() {
+                    record.start = Some(parse_datetime(value, &self.datetime_format).ok_or_else(error)?);
+                },
+                RecordFieldName::Activity => record.activity = self.parse_duration(value).map_err(|_| error())?,
+                RecordFieldName::Rest => record.rest = self.parse_duration(value).map_err(|_| error())?,
+                RecordFieldName::Note => record.note = value.to_string(),
+            }
+        }
+        Ok(record)
+    }
+
+    fn format_record(&self, record: &Record) -> String {
+        self.fields.iter()
+            .map(|field| match field {
+                RecordFieldName::Start => format_datetime(record.start, &self.datetime_format),
+                RecordFieldName::Activity => self.format_duration(record.activity),
+                RecordFieldName::Rest => self.format_duration(record.rest),
+                RecordFieldName::Note => record.note.clone(),
+            })
+            .collect::<Vec<_>>()
+            .join(&self.delimiter)
+            .trim_end()
+            .to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn datetime(source: &str) -> Option<DateTime<Local>> {
+        parse_datetime(source, Record::START_DATETIME_FORMAT)
+    }
+
+    #[test]
+    fn bracket_format() {
+        let format = BracketFormat::default();
+        let record = format.parse_record("[  2018-07-26 23:03:41,  25  ( -16 ) ]  Some note\n").unwrap();
+        assert_eq!(Record {
+            start: datetime("2018-07-26 23:03:41"),
+            activity: Some(Duration::minutes(25)),
+            rest: Some(Duration::minutes(-16)),
+            note: "Some note".to_string(),
+        }, record);
+        assert_eq!("[2018-07-26 23:03:41, 25 (-16)] Some note", format.format_record(&record));
+
+        let format = BracketFormat::default().with_datetime_format("%d.%m.%Y %H:%M");
+        let record = format.parse_record("[26.07.2018 23:03, 25] Some note").unwrap();
+        assert_eq!(datetime("2018-07-26 23:03:00"), record.start);
+        assert_eq!("[26.07.2018 23:03, 25] Some note", format.format_record(&record));
+
+        assert!(format.parse_record("Some line").is_err());
+    }
+
+    #[test]
+    fn legacy_format() {
+        let format = DelimitedFormat::legacy();
+        let record = format.parse_record("2018-07-26 23:03 | 25m | 7m | note | with bar\n").unwrap();
+        assert_eq!(Record {
+            start: datetime("2018-07-26 23:03:00"),
+            activity: Some(Duration::minutes(25)),
+            rest: Some(Duration::minutes(7)),
+            note: "note | with bar".to_string(),
+        }, record);
+        assert_eq!("2018-07-26 23:03 | 25m | 7m | note | with bar", format.format_record(&record));
+
+        let record = format.parse_record("2018-07-26 23:03 | 25m |  | ").unwrap();
+        assert_eq!(None, record.rest);
+        assert_eq!("", record.note);
+        assert_eq!("2018-07-26 23:03 | 25m |  |", format.format_record(&record));
+
+        assert!(format.parse_record("2018-07-26 23:03 | 25m").is_err());
+        assert!(format.parse_record("26.07.2018 | 25m | 7m | note").is_err());
+        assert!(format.parse_record("2018-07-26 23:03 | 25h | 7m | note").is_err());
+    }
+
+    #[test]
+    fn delimited_field_order() {
+        let format = DelimitedFormat::new(";", vec![
+            RecordFieldName::Note,
+            RecordFieldName::Activity,
+            RecordFieldName::Start,
+        ]);
+        let record = format.parse_record("Some note;42;2018-08-16 13:52:43").unwrap();
+        assert_eq!(Record {
+            start: datetime("2018-08-16 13:52:43"),
+            activity: Some(Duration::minutes(42)),
+            rest: None,
+            note: "Some note".to_string(),
+        }, record);
+        assert_eq!("Some note;42;2018-08-16 13:52:43", format.format_record(&record));
+    }
+}
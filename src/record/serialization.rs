@@ -0,0 +1,155 @@
+//! Serde representation of records, enabled by the `serde` feature.
+//!
+//! A `Record` is an object with `start` as an RFC 3339 / ISO 8601 string,
+//! `activity` and `rest` as signed whole minutes and `note` as a string.
+//! Missing values are `null` and absent keys deserialize to `None`:
+//!
+//! ```json
+//! {"start": "2018-08-16T15:40:25+03:00", "activity": 42, "rest": -5, "note": "Note 2"}
+//! ```
+//!
+//! An `Item` is `{"record": {..}}`, `{"comment": ".."}`, `{"section": ".."}`
+//! or `{"some_line": ".."}` and a
+//! `RecordFieldType` is a single-key object such as `{"rest": -5}`.
+
+use chrono::{DateTime, Local, Duration};
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use crate::record::RecordFieldType;
+
+pub mod start {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(start: &Option<DateTime<Local>>, serializer: S) -> Result<S::Ok, S::Error> {
+        start.map(|dt| dt.to_rfc3339()).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<DateTime<Local>>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|source| DateTime::parse_from_rfc3339(&source)
+                .map(|dt| dt.with_timezone(&Local))
+                .map_err(serde::de::Error::custom))
+            .transpose()
+    }
+}
+
+pub mod minutes {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(duration: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error> {
+        duration.map(|duration| duration.num_minutes()).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Duration>, D::Error> {
+        Ok(Option::<i64>::deserialize(deserializer)?.map(Duration::minutes))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum FieldTypeRepr {
+    Start(#[serde(with = "start")] Option<DateTime<Local>>),
+    Activity(#[serde(with = "minutes")] Option<Duration>),
+    Rest(#[serde(with = "minutes")] Option<Duration>),
+    Note(String),
+}
+
+impl Serialize for RecordFieldType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            RecordFieldType::Start(x) => FieldTypeRepr::Start(*x),
+            RecordFieldType::Activity(x) => FieldTypeRepr::Activity(*x),
+            RecordFieldType::Rest(x) => FieldTypeRepr::Rest(*x),
+            RecordFieldType::Note(x) => FieldTypeRepr::Note(x.clone()),
+        }.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for RecordFieldType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(match FieldTypeRepr::deserialize(deserializer)? {
+            FieldTypeRepr::Start(x) => RecordFieldType::Start(x),
+            FieldTypeRepr::Activity(x) => RecordFieldType::Activity(x),
+            FieldTypeRepr::Rest(x) => RecordFieldType::Rest(x),
+            FieldTypeRepr::Note(x) => RecordFieldType::Note(x),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDateTime, TimeZone};
+    use serde_json::json;
+    use crate::record::Record;
+    use crate::journal::file::Item;
+    use super::*;
+
+    fn record(start: &str, activity: Option<i64>, rest: Option<i64>, note: &str) -> Record {
+        Record {
+            start: NaiveDateTime::parse_from_str(start, Record::START_DATETIME_FORMAT).ok()
+                .and_then(|naive| Local.from_local_datetime(&naive).earliest()),
+            activity: activity.map(Duration::minutes),
+            rest: rest.map(Duration::minutes),
+            note: note.to_string(),
+        }
+    }
+
+    #[test]
+    fn record_json() {
+        let source = record("2018-08-16 15:40:25", Some(42), Some(-5), "Note 2");
+        let value = serde_json::to_value(&source).unwrap();
+        assert_eq!(json!({
+            "start": source.start.unwrap().to_rfc3339(),
+            "activity": 42,
+            "rest": -5,
+            "note": "Note 2",
+        }), value);
+        assert_eq!(source, serde_json::from_value::<Record>(value).unwrap());
+
+        let source = Record::default();
+        let value = serde_json::to_value(&source).unwrap();
+        assert_eq!(json!({ "start": null, "activity": null, "rest": null, "note": "" }), value);
+        assert_eq!(source, serde_json::from_value::<Record>(value).unwrap());
+
+        let parsed = serde_json::from_str::<Record>(r#"{"start":"2018-08-16T10:52:43Z","activity":85}"#).unwrap();
+        assert_eq!(parsed.start.unwrap().timestamp(), 1_534_416_763);
+        assert_eq!(parsed.activity, Some(Duration::minutes(85)));
+        assert_eq!(parsed.rest, None);
+        assert_eq!(parsed.note, "");
+
+        assert!(serde_json::from_str::<Record>(r#"{"start":"2018-08-16 13:52:43"}"#).is_err());
+    }
+
+    #[test]
+    fn item_json() {
+        let items = vec![
+            Item::Record(record("2018-08-16 18:12:01", Some(85), None, "Note 3")),
+            Item::Record(record("", None, Some(-16), "")),
+            Item::SomeLine("Some line".to_string()),
+        ];
+        let json = serde_json::to_string(&items).unwrap();
+        assert_eq!(items, serde_json::from_str::<Vec<Item>>(&json).unwrap());
+
+        let value = serde_json::to_value(&items[2]).unwrap();
+        assert_eq!(json!({ "some_line": "Some line" }), value);
+    }
+
+    #[test]
+    fn field_type_json() {
+        let fields = vec![
+            RecordFieldType::Start(None),
+            RecordFieldType::Activity(Some(Duration::minutes(42))),
+            RecordFieldType::Rest(Some(Duration::minutes(-5))),
+            RecordFieldType::Note("Note".to_string()),
+        ];
+        let value = serde_json::to_value(&fields).unwrap();
+        assert_eq!(json!([
+            { "start": null },
+            { "activity": 42 },
+            { "rest": -5 },
+            { "note": "Note" },
+        ]), value);
+
+        let parsed = serde_json::from_value::<Vec<RecordFieldType>>(value).unwrap();
+        assert_eq!(serde_json::to_value(&fields).unwrap(), serde_json::to_value(&parsed).unwrap());
+    }
+}
@@ -0,0 +1,128 @@
+pub mod org;
+pub mod markdown;
+
+use std::ops::AddAssign;
+use chrono::{Duration, NaiveDate};
+use crate::record::Record;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Totals {
+    pub activity: Duration,
+    pub rest: Duration,
+    pub records: usize,
+}
+
+impl Default for Totals {
+    fn default() -> Self {
+        Totals {
+            activity: Duration::zero(),
+            rest: Duration::zero(),
+            records: 0,
+        }
+    }
+}
+
+impl Totals {
+    pub fn add(&mut self, record: &Record) {
+        self.activity += record.activity.unwrap_or_else(Duration::zero);
+        self.rest += record.rest.unwrap_or_else(Duration::zero);
+        self.records += 1;
+    }
+
+    pub fn of<'a, I: IntoIterator<Item = &'a Record>>(records: I) -> Self {
+        let mut totals = Totals::default();
+        for record in records {
+            totals.add(record);
+        }
+        totals
+    }
+}
+
+impl AddAssign for Totals {
+    fn add_assign(&mut self, other: Totals) {
+        self.activity += other.activity;
+        self.rest += other.rest;
+        self.records += other.records;
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Day {
+    /// `None` for the records without a start.
+    pub date: Option<NaiveDate>,
+    pub records: Vec<Record>,
+    pub totals: Totals,
+}
+
+/// Groups the records by the date of their start; the undated records come first.
+pub fn group_by_day<I: IntoIterator<Item = Record>>(records: I) -> Vec<Day> {
+    let mut days: Vec<Day> = Vec::new();
+    for record in records {
+        let date = record.start.map(|start| start.naive_local().date());
+        let idx = match days.binary_search_by_key(&date, |day| day.date) {
+            Ok(idx) => idx,
+            Err(idx) => {
+                days.insert(idx, Day { date, records: Vec::new(), totals: Totals::default() });
+                idx
+            },
+        };
+        days[idx].totals.add(&record);
+        days[idx].records.push(record);
+    }
+    for day in &mut days {
+        day.records.sort_by_key(|record| record.start);
+    }
+    days
+}
+
+pub fn format_duration(duration: Duration) -> String {
+    let minutes = duration.num_minutes();
+    let sign = if minutes < 0 { "-" } else { "" };
+    format!("{}{}:{:02}", sign, minutes.abs() / 60, minutes.abs() % 60)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use chrono::{Local, NaiveDateTime, TimeZone};
+
+    pub fn record(start: &str, activity: Option<i64>, rest: Option<i64>, note: &str) -> Record {
+        Record {
+            start: NaiveDateTime::parse_from_str(start, Record::START_DATETIME_FORMAT).ok()
+                .and_then(|naive| Local.from_local_datetime(&naive).earliest()),
+            activity: activity.map(Duration::minutes),
+            rest: rest.map(Duration::minutes),
+            note: note.to_string(),
+        }
+    }
+
+    pub fn records() -> Vec<Record> {
+        vec![
+            record("2018-08-17 09:00:00", Some(30), None, "Note 4"),
+            record("2018-08-16 15:40:25", Some(42), Some(-5), "Note 2"),
+            record("2018-08-16 13:52:43", Some(42), Some(1), "Note 1"),
+            record("", Some(85), None, "Without start"),
+            record("2018-08-16 18:12:01", None, None, "Note | 3"),
+        ]
+    }
+
+    #[test]
+    fn grouping() {
+        let days = group_by_day(records());
+        assert_eq!(3, days.len());
+        assert_eq!(None, days[0].date);
+        assert_eq!(Totals { activity: Duration::minutes(85), rest: Duration::zero(), records: 1 }, days[0].totals);
+        assert_eq!(NaiveDate::from_ymd_opt(2018, 8, 16), days[1].date);
+        assert_eq!(vec!["Note 1", "Note 2", "Note | 3"], days[1].records.iter().map(|r| r.note.as_str()).collect::<Vec<_>>());
+        assert_eq!(Totals { activity: Duration::minutes(84), rest: Duration::minutes(-4), records: 3 }, days[1].totals);
+        assert_eq!(Totals { activity: Duration::minutes(30), rest: Duration::zero(), records: 1 }, days[2].totals);
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!("0:00", format_duration(Duration::zero()));
+        assert_eq!("1:24", format_duration(Duration::minutes(84)));
+        assert_eq!("-0:04", format_duration(Duration::minutes(-4)));
+        assert_eq!("25:00", format_duration(Duration::hours(25)));
+    }
+}
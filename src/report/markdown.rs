@@ -0,0 +1,125 @@
+use chrono::{DateTime, Duration, Local};
+use crate::record::Record;
+use crate::report::{group_by_day, format_duration, Totals};
+use crate::rounding::RoundingPolicy;
+
+fn escape(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+fn row(cells: &[String]) -> String {
+    format!("| {} |\n", cells.join(" | "))
+}
+
+fn adjustment_row(label: &str, adjustment: Duration) -> String {
+    let sign = if adjustment > Duration::zero() { "+" } else { "" };
+    row(&[
+        String::new(),
+        String::new(),
+        String::new(),
+        format!("{}{}", sign, format_duration(adjustment)),
+        String::new(),
+        label.to_string(),
+    ])
+}
+
+pub fn render<I: IntoIterator<Item = Record>>(records: I) -> String {
+    render_rounded(records, &RoundingPolicy::none())
+}
+
+pub fn render_rounded<I: IntoIterator<Item = Record>>(records: I, rounding: &RoundingPolicy) -> String {
+    let mut output = row(&[
+        "Date".to_string(),
+        "Start".to_string(),
+        "End".to_string(),
+        "Activity".to_string(),
+        "Rest".to_string(),
+        "Note".to_string(),
+    ]);
+    output += "|------|-------|-----|---------:|-----:|------|\n";
+
+    let mut total = Totals::default();
+    let mut adjustment = Duration::zero();
+    for day in group_by_day(records) {
+        let date = day.date.map(|date| date.format("%Y-%m-%d").to_string()).unwrap_or_else(|| "Undated".to_string());
+        for record in &day.records {
+            let time = |datetime: Option<DateTime<Local>>| datetime
+                .map(|dt| dt.format("%H:%M").to_string())
+                .unwrap_or_default();
+            output += &row(&[
+                date.clone(),
+                time(record.start),
+                time(record.start.and_then(|start| record.activity.map(|activity| start + activity))),
+                record.activity.map(format_duration).unwrap_or_default(),
+                record.rest.map(format_duration).unwrap_or_default(),
+                escape(&record.note),
+            ]);
+        }
+        output += &row(&[
+            format!("**{}**", date),
+            String::new(),
+            String::new(),
+            format!("**{}**", format_duration(day.totals.activity)),
+            format!("**{}**", format_duration(day.totals.rest)),
+            "**Subtotal**".to_string(),
+        ]);
+        let rounded = rounding.rounded_activity(&day.records);
+        if rounded.adjustment() != Duration::zero() {
+            output += &adjustment_row("Rounding adjustment", rounded.adjustment());
+            adjustment += rounded.adjustment();
+        }
+        total += day.totals;
+    }
+    if adjustment != Duration::zero() {
+        output += &adjustment_row("Total rounding adjustment", adjustment);
+    }
+    output + &row(&[
+        "**Total**".to_string(),
+        String::new(),
+        String::new(),
+        format!("**{}**", format_duration(total.activity)),
+        format!("**{}**", format_duration(total.rest)),
+        String::new(),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::tests::records;
+
+    #[test]
+    fn render_records() {
+        assert_eq!("| Date | Start | End | Activity | Rest | Note |
+|------|-------|-----|---------:|-----:|------|
+| Undated |  |  | 1:25 |  | Without start |
+| **Undated** |  |  | **1:25** | **0:00** | **Subtotal** |
+| 2018-08-16 | 13:52 | 14:34 | 0:42 | 0:01 | Note 1 |
+| 2018-08-16 | 15:40 | 16:22 | 0:42 | -0:05 | Note 2 |
+| 2018-08-16 | 18:12 |  |  |  | Note \\| 3 |
+| **2018-08-16** |  |  | **1:24** | **-0:04** | **Subtotal** |
+| 2018-08-17 | 09:00 | 09:30 | 0:30 |  | Note 4 |
+| **2018-08-17** |  |  | **0:30** | **0:00** | **Subtotal** |
+| **Total** |  |  | **3:19** | **-0:04** |  |
+", render(records()));
+    }
+
+    #[test]
+    fn render_rounded_records() {
+        assert_eq!("| Date | Start | End | Activity | Rest | Note |
+|------|-------|-----|---------:|-----:|------|
+| Undated |  |  | 1:25 |  | Without start |
+| **Undated** |  |  | **1:25** | **0:00** | **Subtotal** |
+|  |  |  | +0:05 |  | Rounding adjustment |
+| 2018-08-16 | 13:52 | 14:34 | 0:42 | 0:01 | Note 1 |
+| 2018-08-16 | 15:40 | 16:22 | 0:42 | -0:05 | Note 2 |
+| 2018-08-16 | 18:12 |  |  |  | Note \\| 3 |
+| **2018-08-16** |  |  | **1:24** | **-0:04** | **Subtotal** |
+|  |  |  | +0:06 |  | Rounding adjustment |
+| 2018-08-17 | 09:00 | 09:30 | 0:30 |  | Note 4 |
+| **2018-08-17** |  |  | **0:30** | **0:00** | **Subtotal** |
+|  |  |  | +0:11 |  | Total rounding adjustment |
+| **Total** |  |  | **3:19** | **-0:04** |  |
+", render_rounded(records(), &RoundingPolicy::up(15).per_day()));
+    }
+}
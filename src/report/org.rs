@@ -0,0 +1,89 @@
+use chrono::{DateTime, Local};
+use crate::record::Record;
+use crate::report::{group_by_day, format_duration, Totals};
+
+const TIMESTAMP_FORMAT: &str = "[%Y-%m-%d %a %H:%M]";
+
+fn timestamp(datetime: DateTime<Local>) -> String {
+    datetime.format(TIMESTAMP_FORMAT).to_string()
+}
+
+fn clock(record: &Record) -> Option<String> {
+    let start = record.start?;
+    Some(match record.activity {
+        Some(activity) => format!(
+            "CLOCK: {}--{} => {:>5}",
+            timestamp(start),
+            timestamp(start + activity),
+            format_duration(activity)
+        ),
+        None => format!("CLOCK: {}", timestamp(start)),
+    })
+}
+
+pub fn render<I: IntoIterator<Item = Record>>(records: I) -> String {
+    let mut output = String::new();
+    let mut total = Totals::default();
+    for day in group_by_day(records) {
+        output += &format!(
+            "* {}\n  :PROPERTIES:\n  :ACTIVITY: {}\n  :REST:     {}\n  :END:\n",
+            day.date.map(|date| date.format("%Y-%m-%d %a").to_string()).unwrap_or_else(|| "Undated".to_string()),
+            format_duration(day.totals.activity),
+            format_duration(day.totals.rest)
+        );
+        for record in &day.records {
+            let heading = if record.note.is_empty() { "(no note)" } else { record.note.as_str() };
+            output += &format!("** {}\n", heading);
+            if let Some(clock) = clock(record) {
+                output += &format!("   {}\n", clock);
+            }
+        }
+        total += day.totals;
+    }
+    output + &format!(
+        "# Total activity: {}, rest: {}\n",
+        format_duration(total.activity),
+        format_duration(total.rest)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::tests::records;
+
+    #[test]
+    fn render_records() {
+        assert_eq!("* Undated
+  :PROPERTIES:
+  :ACTIVITY: 1:25
+  :REST:     0:00
+  :END:
+** Without start
+* 2018-08-16 Thu
+  :PROPERTIES:
+  :ACTIVITY: 1:24
+  :REST:     -0:04
+  :END:
+** Note 1
+   CLOCK: [2018-08-16 Thu 13:52]--[2018-08-16 Thu 14:34] =>  0:42
+** Note 2
+   CLOCK: [2018-08-16 Thu 15:40]--[2018-08-16 Thu 16:22] =>  0:42
+** Note | 3
+   CLOCK: [2018-08-16 Thu 18:12]
+* 2018-08-17 Fri
+  :PROPERTIES:
+  :ACTIVITY: 0:30
+  :REST:     0:00
+  :END:
+** Note 4
+   CLOCK: [2018-08-17 Fri 09:00]--[2018-08-17 Fri 09:30] =>  0:30
+# Total activity: 3:19, rest: -0:04
+", render(records()));
+    }
+
+    #[test]
+    fn render_empty() {
+        assert_eq!("# Total activity: 0:00, rest: 0:00\n", render(Vec::new()));
+    }
+}
@@ -0,0 +1,222 @@
+use std::collections::BTreeMap;
+use chrono::{Duration, NaiveDate};
+use crate::record::Record;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoundingMode {
+    Up,
+    Down,
+    Nearest,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoundingScope {
+    Entry,
+    Day,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rounded {
+    pub actual: Duration,
+    pub rounded: Duration,
+}
+
+impl Default for Rounded {
+    fn default() -> Self {
+        Rounded {
+            actual: Duration::zero(),
+            rounded: Duration::zero(),
+        }
+    }
+}
+
+impl Rounded {
+    pub fn adjustment(&self) -> Duration {
+        self.rounded - self.actual
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RoundingPolicy {
+    mode: RoundingMode,
+    step: i64,
+    minimum: Option<Duration>,
+    scope: RoundingScope,
+}
+
+impl Default for RoundingPolicy {
+    fn default() -> Self {
+        RoundingPolicy::none()
+    }
+}
+
+impl RoundingPolicy {
+    pub fn new(mode: RoundingMode, step_minutes: i64) -> Self {
+        RoundingPolicy {
+            mode,
+            step: step_minutes,
+            minimum: None,
+            scope: RoundingScope::Entry,
+        }
+    }
+
+    pub fn none() -> Self {
+        RoundingPolicy::new(RoundingMode::Nearest, 1)
+    }
+
+    pub fn up(step_minutes: i64) -> Self {
+        RoundingPolicy::new(RoundingMode::Up, step_minutes)
+    }
+
+    pub fn down(step_minutes: i64) -> Self {
+        RoundingPolicy::new(RoundingMode::Down, step_minutes)
+    }
+
+    pub fn nearest(step_minutes: i64) -> Self {
+        RoundingPolicy::new(RoundingMode::Nearest, step_minutes)
+    }
+
+    pub fn with_minimum(mut self, minimum: Duration) -> Self {
+        self.minimum = Some(minimum);
+        self
+    }
+
+    pub fn with_scope(mut self, scope: RoundingScope) -> Self {
+        self.scope = scope;
+        self
+    }
+
+    pub fn per_day(self) -> Self {
+        self.with_scope(RoundingScope::Day)
+    }
+
+    pub fn scope(&self) -> RoundingScope {
+        self.scope
+    }
+
+    pub fn round(&self, duration: Duration) -> Duration {
+        let minutes = duration.num_minutes();
+        let step = self.step.max(1);
+        let rounded = match self.mode {
+            RoundingMode::Up => (minutes + step - 1).div_euclid(step) * step,
+            RoundingMode::Down => minutes.div_euclid(step) * step,
+            RoundingMode::Nearest => (minutes + step / 2).div_euclid(step) * step,
+        };
+        let rounded = Duration::minutes(rounded);
+        match self.minimum {
+            Some(minimum) if minutes > 0 && rounded < minimum => minimum,
+            _ => rounded,
+        }
+    }
+
+    fn units(&self, records: &[&Record]) -> Vec<Vec<usize>> {
+        let active = (0..records.len()).filter(|&idx| records[idx].activity.is_some());
+        match self.scope {
+            RoundingScope::Entry => active.map(|idx| vec![idx]).collect(),
+            RoundingScope::Day => {
+                let mut days: BTreeMap<Option<NaiveDate>, Vec<usize>> = BTreeMap::new();
+                for idx in active {
+                    days.entry(records[idx].start.map(|start| start.naive_local().date()))
+                        .or_default()
+                        .push(idx);
+                }
+                days.into_values().collect()
+            },
+        }
+    }
+
+    fn round_unit(&self, records: &[&Record], unit: &[usize]) -> Rounded {
+        let actual = unit.iter()
+            .filter_map(|&idx| records[idx].activity)
+            .fold(Duration::zero(), |sum, activity| sum + activity);
+        Rounded { actual, rounded: self.round(actual) }
+    }
+
+    pub fn rounded_activity<'a, I>(&self, records: I) -> Rounded
+        where I: IntoIterator<Item = &'a Record>,
+    {
+        let records = records.into_iter().collect::<Vec<_>>();
+        let mut total = Rounded::default();
+        for unit in self.units(&records) {
+            let rounded = self.round_unit(&records, &unit);
+            total.actual += rounded.actual;
+            total.rounded += rounded.rounded;
+        }
+        total
+    }
+
+    /// Returns copies of `records` whose activity is rounded; with per day
+    /// rounding the day's adjustment is added to its last record.
+    pub fn round_records<I: IntoIterator<Item = Record>>(&self, records: I) -> Vec<Record> {
+        let mut records = records.into_iter().collect::<Vec<_>>();
+        let adjustments = {
+            let refs = records.iter().collect::<Vec<_>>();
+            self.units(&refs)
+                .into_iter()
+                .filter_map(|unit| Some((*unit.last()?, self.round_unit(&refs, &unit).adjustment())))
+                .collect::<Vec<_>>()
+        };
+        for (idx, adjustment) in adjustments {
+            records[idx].activity = records[idx].activity.map(|activity| activity + adjustment);
+        }
+        records
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::tests::record;
+
+    #[test]
+    fn round() {
+        let minutes = |m| Duration::minutes(m);
+        assert_eq!(minutes(12), RoundingPolicy::up(6).round(minutes(7)));
+        assert_eq!(minutes(6), RoundingPolicy::down(6).round(minutes(7)));
+        assert_eq!(minutes(6), RoundingPolicy::nearest(6).round(minutes(8)));
+        assert_eq!(minutes(12), RoundingPolicy::nearest(6).round(minutes(9)));
+        assert_eq!(minutes(15), RoundingPolicy::up(15).round(minutes(15)));
+        assert_eq!(minutes(7), RoundingPolicy::none().round(minutes(7)));
+        assert_eq!(minutes(30), RoundingPolicy::down(15).with_minimum(minutes(30)).round(minutes(20)));
+        assert_eq!(minutes(0), RoundingPolicy::up(15).with_minimum(minutes(30)).round(minutes(0)));
+        assert_eq!(minutes(-15), RoundingPolicy::up(15).round(minutes(-20)));
+    }
+
+    fn records() -> Vec<Record> {
+        vec![
+            record("2018-08-16 09:00:00", Some(7), None, "Note 1"),
+            record("2018-08-16 10:00:00", Some(8), Some(3), "Note 2"),
+            record("2018-08-16 11:00:00", None, None, "Running"),
+            record("2018-08-17 09:00:00", Some(50), None, "Note 3"),
+        ]
+    }
+
+    #[test]
+    fn rounded_activity() {
+        let records = records();
+        let rounded = RoundingPolicy::up(15).rounded_activity(&records);
+        assert_eq!(Duration::minutes(65), rounded.actual);
+        assert_eq!(Duration::minutes(90), rounded.rounded);
+        assert_eq!(Duration::minutes(25), rounded.adjustment());
+
+        let rounded = RoundingPolicy::up(15).per_day().rounded_activity(&records);
+        assert_eq!(Duration::minutes(75), rounded.rounded);
+        assert_eq!(Duration::minutes(10), rounded.adjustment());
+    }
+
+    #[test]
+    fn round_records() {
+        let rounded = RoundingPolicy::up(15).round_records(records());
+        assert_eq!(
+            vec![Some(15), Some(15), None, Some(60)],
+            rounded.iter().map(|r| r.activity.map(|a| a.num_minutes())).collect::<Vec<_>>()
+        );
+        assert_eq!(Some(Duration::minutes(3)), rounded[1].rest);
+
+        let rounded = RoundingPolicy::up(15).per_day().round_records(records());
+        assert_eq!(
+            vec![Some(7), Some(8), None, Some(60)],
+            rounded.iter().map(|r| r.activity.map(|a| a.num_minutes())).collect::<Vec<_>>()
+        );
+    }
+}
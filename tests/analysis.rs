@@ -0,0 +1,39 @@
+use std::path::PathBuf;
+use file_assertions::{clear_dir, create_file, assert_content};
+use tt_core::{
+    analysis::{Analyzer, FixUp},
+    journal::file::FileJournal,
+};
+
+#[test]
+fn fix_overlaps_and_gaps() {
+    let journal_dir = &["target", "test_analysis", "fix"].iter().collect::<PathBuf>();
+    let journal_file = &journal_dir.join("journal.txt");
+    clear_dir!(journal_dir);
+    create_file!(journal_file, "[2018-08-16 09:00:00, 60 (10)] Review
+[2018-08-16 10:00:00, 30] Call
+[2018-08-16 16:00:00, 30] Email
+");
+    let mut journal = FileJournal::new(journal_file);
+
+    let analysis = Analyzer::new().analyze(journal_records(&journal));
+    assert_eq!(1, analysis.overlaps.len());
+    assert_eq!(1, analysis.gaps.len());
+
+    assert_eq!(1, analysis.fix(&mut journal, &FixUp::TrimOverlaps).unwrap());
+    assert_eq!(1, analysis.fix(&mut journal, &FixUp::FillGaps("Untracked".to_string())).unwrap());
+    let expected = "[2018-08-16 09:00:00, 60 (0)] Review
+[2018-08-16 10:00:00, 30] Call
+[2018-08-16 16:00:00, 30] Email
+[2018-08-16 10:30:00, 330] Untracked
+".to_string();
+    assert_content!(journal_file, expected);
+
+    let analysis = Analyzer::new().analyze(journal_records(&journal));
+    assert!(analysis.overlaps.is_empty());
+    assert!(analysis.gaps.is_empty());
+}
+
+fn journal_records(journal: &FileJournal) -> Vec<tt_core::record::Record> {
+    journal.try_iter().unwrap().filter_map(|item| item.into_record()).collect()
+}
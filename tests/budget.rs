@@ -0,0 +1,73 @@
+use std::path::PathBuf;
+use chrono::{Local, Duration, NaiveDateTime, TimeZone};
+use file_assertions::{clear_dir, create_file};
+use tt_core::{
+    budget::{AlertKind, Budget, Budgets, Period},
+    journal::{Journal, file::FileJournal},
+    record::{Record, RecordMatcher},
+};
+
+fn time(source: &str) -> chrono::DateTime<Local> {
+    let naive = NaiveDateTime::parse_from_str(source, "%Y-%m-%d %H:%M:%S").unwrap();
+    Local.from_local_datetime(&naive).earliest().unwrap()
+}
+
+#[test]
+fn poll_alerts() {
+    let journal_dir = &["target", "test_budget", "poll_alerts"].iter().collect::<PathBuf>();
+    let journal_file = &journal_dir.join("journal.txt");
+    clear_dir!(journal_dir);
+    create_file!(journal_file, "\
+[2018-08-13 09:00:00, 180] Planning #meeting\n\
+[2018-08-14 09:00:00, 300] Workshop #meeting\n\
+[2018-08-15 09:00:00, 120] Feature @tt\n");
+    let mut journal = FileJournal::new(journal_file);
+
+    let mut budgets = Budgets::new()
+        .with_budget(Budget::at_most(RecordMatcher::Tag("meeting".to_string()), Period::Week, Duration::hours(10))
+            .with_name("meetings"))
+        .with_budget(Budget::at_least(RecordMatcher::Project("tt".to_string()), Period::Month, Duration::hours(20)));
+
+    let now = time("2018-08-16 11:00:00");
+    let alerts = budgets.poll(&journal, now).unwrap();
+    assert_eq!(
+        vec![
+            "[approaching] meetings: 8:00 of at most 10:00, 2:00 remaining",
+            "[behind] @tt: 2:00 of at least 20:00, 18:00 remaining",
+        ],
+        alerts.iter().map(|alert| alert.to_string()).collect::<Vec<_>>()
+    );
+    assert!(budgets.poll(&journal, now).unwrap().is_empty());
+
+    journal.add(&Record {
+        start: Some(time("2018-08-16 09:00:00")),
+        activity: Some(Duration::hours(3)),
+        rest: None,
+        note: "Review #meeting".to_string(),
+    }).unwrap();
+    let alerts = budgets.poll(&journal, now).unwrap();
+    assert_eq!(vec![AlertKind::Exceeded], alerts.iter().map(|alert| alert.kind).collect::<Vec<_>>());
+    assert_eq!(2, budgets.alerts(&journal, now).unwrap().len());
+
+    let next_week = time("2018-08-20 09:00:00");
+    assert!(budgets.poll(&journal, next_week).unwrap().iter().all(|alert| alert.kind == AlertKind::Behind));
+    assert!(budgets.progress(&journal, next_week).unwrap()[0].spent.is_zero());
+}
+
+#[test]
+fn same_names() {
+    let journal_dir = &["target", "test_budget", "same_names"].iter().collect::<PathBuf>();
+    let journal_file = &journal_dir.join("journal.txt");
+    clear_dir!(journal_dir);
+    create_file!(journal_file, "[2018-08-16 09:00:00, 180] Planning #meeting\n");
+    let journal = FileJournal::new(journal_file);
+
+    let matcher = RecordMatcher::Tag("meeting".to_string());
+    let mut budgets = Budgets::new()
+        .with_budget(Budget::at_most(matcher.clone(), Period::Day, Duration::hours(2)).with_name("limits"))
+        .with_budget(Budget::at_most(matcher, Period::Week, Duration::hours(2)).with_name("limits"));
+
+    let now = time("2018-08-16 12:00:00");
+    assert_eq!(2, budgets.poll(&journal, now).unwrap().len());
+    assert!(budgets.poll(&journal, now).unwrap().is_empty());
+}
@@ -0,0 +1,122 @@
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use file_assertions::{clear_dir, create_file, assert_content};
+
+fn tt(journal: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_tt"))
+        .arg("--journal")
+        .arg(journal)
+        .args(args)
+        .env_remove("TT_JOURNAL")
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: Output) -> String {
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn start_pause_resume_stop() {
+    let journal_dir = &["target", "test_cli", "start_pause_resume_stop"].iter().collect::<PathBuf>();
+    let journal_file = &journal_dir.join("journal.txt");
+    clear_dir!(journal_dir);
+    create_file!(journal_file, "[2018-08-16 08:00:00, 30] Standup\n");
+
+    stdout(tt(journal_file, &["start", "--at", "2018-08-16 09:00", "Review", "@tt"]));
+    assert_content!(journal_file, "[2018-08-16 08:00:00, 30] Standup\n[2018-08-16 09:00:00, ] Review @tt\n");
+
+    stdout(tt(journal_file, &["pause", "--at", "2018-08-16 09:40"]));
+    assert!(!tt(journal_file, &["pause", "--at", "2018-08-16 09:45"]).status.success());
+    stdout(tt(journal_file, &["resume", "--at", "2018-08-16 09:50"]));
+    assert_content!(journal_file, "[2018-08-16 08:00:00, 30] Standup\n[2018-08-16 09:00:00,  (10)] Review @tt\n");
+
+    stdout(tt(journal_file, &["start", "--at", "2018-08-16 10:30", "Email"]));
+    assert_eq!("[2018-08-16 10:30:00, 15] Email\n", stdout(tt(journal_file, &["stop", "--at", "2018-08-16 10:45"])));
+    assert_content!(journal_file, "\
+[2018-08-16 08:00:00, 30] Standup\n\
+[2018-08-16 09:00:00, 80 (10)] Review @tt\n\
+[2018-08-16 10:30:00, 15] Email\n");
+}
+
+#[test]
+fn add_edit_rm() {
+    let journal_dir = &["target", "test_cli", "add_edit_rm"].iter().collect::<PathBuf>();
+    let journal_file = &journal_dir.join("journal.txt");
+    clear_dir!(journal_dir);
+    create_file!(journal_file, "[2018-08-16 08:00:00, 30] Standup\n[2018-08-16 09:00:00, 60] Review\n");
+
+    stdout(tt(journal_file, &["add", "--start", "2018-08-16 11:00:00", "--activity", "20", "--rest", "5", "Call"]));
+    assert_eq!("[2018-08-16 11:00:00, 20 (5)] Call\n", stdout(tt(journal_file, &["last"])));
+
+    stdout(tt(journal_file, &["edit", "--note", "Standup", "--set-activity", "15", "--set-note", "Daily"]));
+    stdout(tt(journal_file, &["edit", "--offset", "-2", "--set-rest", "10"]));
+    assert_content!(journal_file, "\
+[2018-08-16 08:00:00, 15] Daily\n\
+[2018-08-16 09:00:00, 60 (10)] Review\n\
+[2018-08-16 11:00:00, 20 (5)] Call\n");
+
+    assert_eq!("[2018-08-16 11:00:00, 20 (5)] Call\n", stdout(tt(journal_file, &["rm"])));
+    stdout(tt(journal_file, &["rm", "--start", "2018-08-16 08:00:00"]));
+    assert_content!(journal_file, "[2018-08-16 09:00:00, 60 (10)] Review\n");
+    assert!(!tt(journal_file, &["rm", "--note", "Missing"]).status.success());
+    assert!(!tt(journal_file, &["edit", "--bogus", "1"]).status.success());
+    assert!(!tt(journal_file, &["rm", "Review"]).status.success());
+    assert!(!tt(journal_file, &["stop", "now"]).status.success());
+    assert_content!(journal_file, "[2018-08-16 09:00:00, 60 (10)] Review\n");
+}
+
+#[test]
+fn start_new_journal() {
+    let journal_dir = &["target", "test_cli", "start_new_journal"].iter().collect::<PathBuf>();
+    let journal_file = &journal_dir.join("journal.txt");
+    clear_dir!(journal_dir);
+
+    assert!(!tt(journal_file, &["last"]).status.success());
+    assert_eq!("", stdout(tt(journal_file, &["list"])));
+    stdout(tt(journal_file, &["start", "--at", "2018-08-16 09:00", "Review"]));
+    assert_content!(journal_file, "[2018-08-16 09:00:00, ] Review\n");
+}
+
+#[test]
+fn list_and_report() {
+    let journal_dir = &["target", "test_cli", "list_and_report"].iter().collect::<PathBuf>();
+    let journal_file = &journal_dir.join("journal.txt");
+    clear_dir!(journal_dir);
+    create_file!(journal_file, "[2018-08-16 09:00:00, 60] Review\n[2018-08-17 09:00:00, 30] Call\n");
+
+    assert_eq!("[2018-08-17 09:00:00, 30] Call\n", stdout(tt(journal_file, &["list", "--from", "2018-08-17"])));
+    let report = stdout(tt(journal_file, &["report", "--to", "2018-08-16"]));
+    assert!(report.contains("Review"));
+    assert!(!report.contains("Call"));
+    assert!(stdout(tt(journal_file, &["report", "--format", "org"])).contains("CLOCK"));
+}
+
+#[test]
+fn journal_from_env_and_config() {
+    let journal_dir = &["target", "test_cli", "journal_from_env_and_config"].iter().collect::<PathBuf>();
+    let journal_file = &journal_dir.join("journal.txt");
+    let config_file = &journal_dir.join("tt").join("config");
+    clear_dir!(journal_dir);
+    create_file!(journal_file, "[2018-08-16 09:00:00, 60] Review\n");
+    let journal_path = std::fs::canonicalize(journal_file).unwrap();
+    let config = format!("# tt settings\njournal = {}\n", journal_path.display());
+    std::fs::create_dir_all(journal_dir.join("tt")).unwrap();
+    create_file!(config_file, &config);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_tt"))
+        .arg("last")
+        .env("TT_JOURNAL", &journal_path)
+        .output()
+        .unwrap();
+    assert_eq!("[2018-08-16 09:00:00, 60] Review\n", stdout(output));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_tt"))
+        .arg("last")
+        .env_remove("TT_JOURNAL")
+        .env("XDG_CONFIG_HOME", std::fs::canonicalize(journal_dir).unwrap())
+        .output()
+        .unwrap();
+    assert_eq!("[2018-08-16 09:00:00, 60] Review\n", stdout(output));
+}
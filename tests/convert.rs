@@ -0,0 +1,28 @@
+use std::path::PathBuf;
+use file_assertions::{clear_dir, assert_content};
+use tt_core::{
+    convert::{self, csv::CsvFormat, IssueKind},
+    journal::file::FileJournal,
+};
+
+#[test]
+fn import_into_journal() {
+    let journal_dir = &["target", "test_convert", "import"].iter().collect::<PathBuf>();
+    let journal_file = &journal_dir.join("journal.txt");
+    clear_dir!(journal_dir);
+    let mut journal = FileJournal::new(journal_file);
+
+    let source = "Start date,Start time,End,Activity (min),Rest (min),Note,Tags
+2018-08-16,13:52:43,,42,1,Note 1,work
+2018-08-16,15:40:25,,42,oops,Note 2,
+";
+    let import = CsvFormat::default().import(source.as_bytes()).unwrap();
+    assert_eq!(1, import.write_into(&mut journal).unwrap());
+    assert_content!(journal_file, "[2018-08-16 13:52:43, 42 (1)] Note 1 #work\n");
+    assert_eq!(vec![(3, IssueKind::Skipped)], import.skipped().map(|issue| (issue.position, issue.kind)).collect::<Vec<_>>());
+
+    let data = "inc 20180816T100000Z - 20180816T103000Z # work # \"Standup\"\n";
+    let import = convert::timewarrior::import(data.as_bytes()).unwrap();
+    assert_eq!(1, import.write_into(&mut journal).unwrap());
+    assert!(import.issues.is_empty());
+}
@@ -5,10 +5,18 @@ use tt_core::{
     record::{
         Record,
         RecordFieldType,
+        format::DelimitedFormat,
     },
     journal::{
         Journal,
-        file::FileJournal,
+        dedupe::{Dedupe, Equality, Keep},
+        file::{
+            FileJournal,
+            history::{History, RetentionPolicy, DiffLine},
+            header::Header,
+            migration::SourceFormat,
+            bulk::BulkEdit,
+        },
     },
 };
 
@@ -169,4 +177,430 @@ fn update_record() {
 [2018-08-16 18:12:01, 85 (-17)] Note 3
 [2018-08-20 22:30:15, 85] Note 4
 ");
-}
\ No newline at end of file
+}
+
+#[test]
+fn history_snapshots() {
+    let journal_dir = &["target", "test_file_journal", "history"].iter().collect::<PathBuf>();
+    let journal_file = &journal_dir.join("journal.txt");
+    let history_dir = &journal_dir.join("history");
+    clear_dir!(journal_dir);
+    let mut journal = FileJournal::new(journal_file)
+        .with_history(History::new(history_dir).with_retention(RetentionPolicy::default().keep_last(2)));
+
+    let record = Record {
+        note: "Note 1".to_string(),
+        ..Default::default()
+    };
+    journal.add(&record).expect("Can't add record to journal");
+    assert!(journal.snapshots().unwrap().is_empty());
+
+    journal.add(&Record { note: "Note 2".to_string(), ..Default::default() })
+        .expect("Can't add record to journal");
+    let snapshots = journal.snapshots().unwrap();
+    assert_eq!(1, snapshots.len());
+    assert_content!(&snapshots[0].path, "[, ] Note 1\n");
+
+    assert!(journal.update(&[RecordFieldType::Note("Note 1".to_string())], None, |mut record| {
+        record.note = "Note 3".to_string();
+        Some(record)
+    }).unwrap());
+    assert!(journal.remove(&[RecordFieldType::Note("Note 2".to_string())], None, |_| true).unwrap());
+    assert_content!(journal_file, "[, ] Note 3\n");
+
+    let snapshots = journal.snapshots().unwrap();
+    assert_eq!(2, snapshots.len());
+    assert_content!(&snapshots[0].path, "[, ] Note 1\n[, ] Note 2\n");
+    assert_content!(&snapshots[1].path, "[, ] Note 3\n[, ] Note 2\n");
+
+    assert_eq!(vec![
+        DiffLine::Removed("[, ] Note 1".to_string()),
+        DiffLine::Removed("[, ] Note 2".to_string()),
+        DiffLine::Added("[, ] Note 3".to_string()),
+    ], journal.diff_snapshot(&snapshots[0]).unwrap());
+
+    journal.restore_snapshot(&snapshots[0]).unwrap();
+    assert_content!(journal_file, "[, ] Note 1\n[, ] Note 2\n");
+
+    let snapshots = journal.snapshots().unwrap();
+    assert_eq!(2, snapshots.len());
+    assert_content!(&snapshots[1].path, "[, ] Note 3\n");
+}
+
+#[test]
+fn legacy_format() {
+    let journal_dir = &["target", "test_file_journal", "legacy_format"].iter().collect::<PathBuf>();
+    let journal_file = &journal_dir.join("journal.txt");
+    clear_dir!(journal_dir);
+    let mut journal = FileJournal::new(journal_file).with_format(DelimitedFormat::legacy());
+
+    create_file!(journal_file, r"2018-07-26 23:03 | 25m | 7m | Note 1
+[2018-08-16 13:52:43, 42 (1)] Note 2
+");
+
+    let record = journal.get(&[], None).expect("Can't get record from journal");
+    assert_eq!(Some(Record {
+        start: Local.datetime_from_str("2018-07-26 23:03:00", Record::START_DATETIME_FORMAT).ok(),
+        activity: Some(Duration::minutes(25)),
+        rest: Some(Duration::minutes(7)),
+        note: "Note 1".to_string(),
+    }), record);
+    assert_eq!(None, journal.get(&[], Some(1)).expect("Can't get record from journal"));
+
+    assert!(!journal.update(&[], Some(-1), Some).unwrap());
+    assert!(journal.update(&[], None, |mut record| {
+        record.rest = None;
+        Some(record)
+    }).unwrap());
+    journal.add(&Record { note: "Note 3".to_string(), ..Default::default() })
+        .expect("Can't add record to journal");
+    assert_content!(journal_file, r"2018-07-26 23:03 | 25m |  | Note 1
+[2018-08-16 13:52:43, 42 (1)] Note 2
+ |  |  | Note 3
+");
+}
+
+#[test]
+fn normalize() {
+    let journal_dir = &["target", "test_file_journal", "normalize"].iter().collect::<PathBuf>();
+    let journal_file = &journal_dir.join("journal.txt");
+    clear_dir!(journal_dir);
+    let mut journal = FileJournal::new(journal_file);
+
+    create_file!(journal_file, r"# Evening
+[  2018-07-26 23:03:41,  25  ( -16 ) ] Note 3
+# Morning
+[2018-07-26 09:00:00, 30] Note 1
+[2018-07-26 09:00:00, 10] Note 2
+");
+
+    assert!(!journal.normalize(|_| false).unwrap());
+    assert!(journal.normalize(|changes| {
+        assert_eq!(vec![
+            DiffLine::Removed("# Evening".to_string()),
+            DiffLine::Removed("[  2018-07-26 23:03:41,  25  ( -16 ) ] Note 3".to_string()),
+            DiffLine::Same("# Morning".to_string()),
+            DiffLine::Same("[2018-07-26 09:00:00, 30] Note 1".to_string()),
+            DiffLine::Same("[2018-07-26 09:00:00, 10] Note 2".to_string()),
+            DiffLine::Added("# Evening".to_string()),
+            DiffLine::Added("[2018-07-26 23:03:41, 25 (-16)] Note 3".to_string()),
+        ], changes);
+        true
+    }).unwrap());
+    assert_content!(journal_file, r"# Morning
+[2018-07-26 09:00:00, 30] Note 1
+[2018-07-26 09:00:00, 10] Note 2
+# Evening
+[2018-07-26 23:03:41, 25 (-16)] Note 3
+");
+    assert_eq!(vec!["journal.txt".to_string()], std::fs::read_dir(journal_dir).unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect::<Vec<_>>());
+    assert!(!journal.normalize(|_| true).unwrap());
+
+    create_file!(journal_file, r"==  Day  ==
+// Morning   notes
+[2018-07-26 09:00:00,  30] Note 1
+#tag-like line
+[2018-07-26 08:00:00, 10] Note 0
+");
+    assert!(journal.normalize(|_| true).unwrap());
+    assert_content!(journal_file, r"==  Day  ==
+#tag-like line
+[2018-07-26 08:00:00, 10] Note 0
+// Morning   notes
+[2018-07-26 09:00:00, 30] Note 1
+");
+}
+
+#[test]
+fn sections() {
+    let journal_dir = &["target", "test_file_journal", "sections"].iter().collect::<PathBuf>();
+    let journal_file = &journal_dir.join("journal.txt");
+    clear_dir!(journal_dir);
+
+    create_file!(journal_file, r"== 2018-08-16 ==
+// Morning
+[2018-08-16 09:00:00, 30] Standup
+== 2018-08-17 ==
+[2018-08-17 09:00:00, 15] Standup
+");
+
+    let mut journal = FileJournal::new(journal_file).with_section("2018-08-16");
+    let query = [RecordFieldType::Note("Standup".to_string())];
+    let record = journal.get(&query, None).unwrap().unwrap();
+    assert_eq!(Some(Duration::minutes(30)), record.activity);
+    assert!(journal.update(&query, None, |mut record| {
+        record.rest = Some(Duration::minutes(5));
+        Some(record)
+    }).unwrap());
+    journal.add(&Record { note: "Review".to_string(), ..Default::default() }).unwrap();
+
+    let mut journal = FileJournal::new(journal_file).with_section("2018-08-17");
+    let record = journal.get(&query, None).unwrap().unwrap();
+    assert_eq!(Some(Duration::minutes(15)), record.activity);
+    journal.add(&Record { note: "Lunch".to_string(), ..Default::default() }).unwrap();
+
+    assert_content!(journal_file, r"== 2018-08-16 ==
+// Morning
+[2018-08-16 09:00:00, 30 (5)] Standup
+[, ] Review
+== 2018-08-17 ==
+[2018-08-17 09:00:00, 15] Standup
+[, ] Lunch
+");
+
+    let journal = FileJournal::new(journal_file).with_section("2018-08-17");
+    assert_eq!("Lunch", journal.get(&[], Some(-1)).unwrap().unwrap().note);
+    assert_eq!("Standup", journal.get(&[], Some(-2)).unwrap().unwrap().note);
+    let journal = FileJournal::new(journal_file).with_section("2018-08-16");
+    assert_eq!("Review", journal.get(&[], Some(-1)).unwrap().unwrap().note);
+
+    let mut journal = FileJournal::new(journal_file).with_section("2018-08-18");
+    journal.add(&Record { note: "Retro".to_string(), ..Default::default() }).unwrap();
+    assert_content!(journal_file, r"== 2018-08-16 ==
+// Morning
+[2018-08-16 09:00:00, 30 (5)] Standup
+[, ] Review
+== 2018-08-17 ==
+[2018-08-17 09:00:00, 15] Standup
+[, ] Lunch
+== 2018-08-18 ==
+[, ] Retro
+");
+}
+
+#[test]
+fn header() {
+    let journal_dir = &["target", "test_file_journal", "header"].iter().collect::<PathBuf>();
+    let journal_file = &journal_dir.join("journal.txt");
+    clear_dir!(journal_dir);
+
+    let mut journal = FileJournal::new(journal_file).with_header(Header::default().with_owner("Jo Doe"));
+    journal.add(&Record { note: "Note 2".to_string(), ..Default::default() }).unwrap();
+    journal.add(&Record { note: "Note 1".to_string(), ..Default::default() }).unwrap();
+    assert_content!(journal_file, r"#! version: 1
+#! owner: Jo Doe
+[, ] Note 2
+[, ] Note 1
+");
+
+    let mut journal = FileJournal::open(journal_file).unwrap();
+    assert_eq!(Some("Jo Doe"), journal.header().and_then(|header| header.owner.as_deref()));
+    assert!(journal.update(&[RecordFieldType::Note("Note 2".to_string())], None, |mut record| {
+        record.start = Local.datetime_from_str("2018-08-16 10:00:00", Record::START_DATETIME_FORMAT).ok();
+        Some(record)
+    }).unwrap());
+    assert!(journal.normalize(|_| true).unwrap());
+    assert_content!(journal_file, r"#! version: 1
+#! owner: Jo Doe
+[, ] Note 1
+[2018-08-16 10:00:00, ] Note 2
+");
+
+    journal.set_header(Header::default()).unwrap();
+    assert_content!(journal_file, r"#! version: 1
+[, ] Note 1
+[2018-08-16 10:00:00, ] Note 2
+");
+    assert!(!journal.normalize(|_| true).unwrap());
+
+    create_file!(journal_file, "[, ] Note 1\n");
+    let mut journal = FileJournal::new(journal_file).with_header(Header::default().with_owner("Jo Doe"));
+    assert!(journal.update(&[], Some(-1), |record| Some(Record { note: "Note 3".to_string(), ..record })).unwrap());
+    journal.add(&Record { note: "Note 2".to_string(), ..Default::default() }).unwrap();
+    assert_content!(journal_file, r"#! version: 1
+#! owner: Jo Doe
+[, ] Note 3
+[, ] Note 2
+");
+
+    let history_dir = &journal_dir.join("history");
+    create_file!(journal_file, "[, ] Note 1\n");
+    let mut journal = FileJournal::new(journal_file)
+        .with_header(Header::default())
+        .with_history(History::new(history_dir));
+    journal.add(&Record { note: "Note 2".to_string(), ..Default::default() }).unwrap();
+    assert_content!(journal_file, "#! version: 1\n[, ] Note 1\n[, ] Note 2\n");
+    let snapshots = journal.snapshots().unwrap();
+    assert_eq!(1, snapshots.len());
+    assert_eq!("[, ] Note 1\n", snapshots[0].content().unwrap());
+}
+
+#[test]
+fn migrate() {
+    let journal_dir = &["target", "test_file_journal", "migrate"].iter().collect::<PathBuf>();
+    let journal_file = &journal_dir.join("journal.txt");
+    clear_dir!(journal_dir);
+    let source = r"2018-07-26 23:03 | 25m | 7m | Note 1
+// Comment
+2018-07-27 09:10 | 5m |  | Note 2
+";
+    create_file!(journal_file, source);
+
+    let mut journal = FileJournal::new(journal_file).with_format(DelimitedFormat::legacy());
+    let migration = journal.migration().unwrap();
+    assert_eq!(SourceFormat::Legacy, migration.from);
+    assert_eq!(5, migration.changes.iter().filter(|line| !matches!(line, DiffLine::Same(_))).count());
+    assert_content!(journal_file, source);
+
+    let backup = journal.migrate().unwrap().expect("Journal is not migrated");
+    assert_content!(&backup, source);
+    assert_content!(journal_file, r"#! version: 1
+[2018-07-26 23:03:00, 25 (7)] Note 1
+// Comment
+[2018-07-27 09:10:00, 5] Note 2
+");
+    assert_eq!(Some(Duration::minutes(5)), journal.get(&[], Some(2)).unwrap().and_then(|record| record.activity));
+    assert_eq!(None, journal.migrate().unwrap());
+
+    create_file!(journal_file, source);
+    let mut journal = FileJournal::new(journal_file).with_format(DelimitedFormat::legacy());
+    let second_backup = journal.migrate().unwrap().expect("Journal is not migrated");
+    assert_ne!(backup, second_backup);
+    assert_content!(&backup, source);
+    assert_content!(&second_backup, source);
+
+    let bracket_file = &journal_dir.join("bracket.txt");
+    create_file!(bracket_file, "[, ] Note\n");
+    let mut journal = FileJournal::new(bracket_file);
+    assert_eq!(None, journal.migrate().unwrap());
+    assert_content!(bracket_file, "[, ] Note\n");
+}
+
+#[test]
+fn merge_copies() {
+    let journal_dir = &["target", "test_file_journal", "merge_copies"].iter().collect::<PathBuf>();
+    let base_file = &journal_dir.join("base.txt");
+    let theirs_file = &journal_dir.join("theirs.txt");
+    let journal_file = &journal_dir.join("journal.txt");
+    clear_dir!(journal_dir);
+    create_file!(base_file, "[2018-08-16 09:00:00, 30] Standup\n[2018-08-16 10:00:00, 60] Review\n");
+    create_file!(theirs_file, "[2018-08-16 09:00:00, 30] Standup\n[2018-08-16 10:00:00, 45] Review\n[2018-08-16 11:00:00, 5] Theirs\n");
+    create_file!(journal_file, "[2018-08-16 09:00:00, 30] Standup\n[2018-08-16 10:00:00, 50] Review\n");
+
+    let mut journal = FileJournal::new(journal_file);
+    let result = journal.merge_with(base_file, theirs_file).unwrap();
+    assert_eq!(1, result.conflicts.len());
+    let conflict = &result.conflicts[0];
+    assert_eq!(Some(Duration::minutes(50)), conflict.ours.as_ref().and_then(|record| record.activity));
+    assert_eq!(Some(Duration::minutes(45)), conflict.theirs.as_ref().and_then(|record| record.activity));
+    assert_content!(journal_file, "[2018-08-16 09:00:00, 30] Standup\n[2018-08-16 10:00:00, 50] Review\n[2018-08-16 11:00:00, 5] Theirs\n");
+}
+
+#[test]
+fn dedupe() {
+    let journal_dir = &["target", "test_file_journal", "dedupe"].iter().collect::<PathBuf>();
+    let journal_file = &journal_dir.join("journal.txt");
+    clear_dir!(journal_dir);
+    create_file!(journal_file, r"[2018-08-16 09:00:00, 30] Standup
+# comment
+[2018-08-16 09:00:00, 35] Standup
+[2018-08-16 10:00:00, 60] Review
+[2018-08-16 09:00:00, 30] Standup
+");
+    let mut journal = FileJournal::new(journal_file);
+
+    let removed = Dedupe::new().apply(&mut journal).unwrap();
+    assert_eq!(vec![Some(Duration::minutes(30))], removed.iter().map(|record| record.activity).collect::<Vec<_>>());
+    assert_content!(journal_file, r"[2018-08-16 09:00:00, 30] Standup
+# comment
+[2018-08-16 09:00:00, 35] Standup
+[2018-08-16 10:00:00, 60] Review
+");
+
+    let removed = Dedupe::new()
+        .with_equality(Equality::StartAndNote)
+        .with_keep(Keep::Longest)
+        .apply(&mut journal)
+        .unwrap();
+    assert_eq!(1, removed.len());
+    assert_content!(journal_file, r"# comment
+[2018-08-16 09:00:00, 35] Standup
+[2018-08-16 10:00:00, 60] Review
+");
+}
+
+#[test]
+fn split_and_merge() {
+    let journal_dir = &["target", "test_file_journal", "split_and_merge"].iter().collect::<PathBuf>();
+    let journal_file = &journal_dir.join("journal.txt");
+    clear_dir!(journal_dir);
+    create_file!(journal_file, r"[2018-08-16 09:00:00, 60 (10)] Review
+# comment
+[2018-08-16 11:00:00, 15] Review
+[2018-08-16 12:00:00, 30] Lunch");
+    let mut journal = FileJournal::new(journal_file);
+    let time = Local.datetime_from_str("2018-08-16 09:20:00", Record::START_DATETIME_FORMAT).unwrap();
+
+    assert!(journal.split_record(&[], None, time).unwrap());
+    assert!(!journal.split_record(&[], Some(-1), time).unwrap());
+    assert_content!(journal_file, r"[2018-08-16 09:00:00, 20] Review
+[2018-08-16 09:20:00, 40 (10)] Review
+# comment
+[2018-08-16 11:00:00, 15] Review
+[2018-08-16 12:00:00, 30] Lunch");
+
+    let query = [RecordFieldType::Start(Some(time))];
+    assert!(journal.merge_records(&query, None).unwrap());
+    assert_content!(journal_file, r"[2018-08-16 09:00:00, 20] Review
+# comment
+[2018-08-16 09:20:00, 55 (60)] Review
+[2018-08-16 12:00:00, 30] Lunch");
+    assert!(!journal.merge_records(&query, None).unwrap());
+    assert!(!journal.merge_records(&[], Some(-1)).unwrap());
+
+    create_file!(journal_file, "[2018-08-16 09:00:00, 60] Review\n");
+    let mut journal = FileJournal::new(journal_file).with_header(Header::default());
+    assert!(journal.split_record(&[], None, time).unwrap());
+    assert!(journal.merge_records(&[], None).unwrap());
+    assert_content!(journal_file, "#! version: 1\n[2018-08-16 09:00:00, 60] Review\n");
+}
+
+#[test]
+fn bulk_edit() {
+    let journal_dir = &["target", "test_file_journal", "bulk_edit"].iter().collect::<PathBuf>();
+    let journal_file = &journal_dir.join("journal.txt");
+    clear_dir!(journal_dir);
+    let source = r"[2018-08-16 09:00:00, 30] Standup #wrok
+# comment
+[2018-08-16 10:00:00, 60] Review #wrok
+[2018-08-17 09:00:00, 30] Standup #wrok
+";
+    create_file!(journal_file, source);
+    let mut journal = FileJournal::new(journal_file);
+    let edit = BulkEdit::new()
+        .between(
+            Local.datetime_from_str("2018-08-16 00:00:00", Record::START_DATETIME_FORMAT).ok(),
+            Local.datetime_from_str("2018-08-17 00:00:00", Record::START_DATETIME_FORMAT).ok(),
+        )
+        .shift(Duration::minutes(-90))
+        .retag("wrok", "work");
+
+    let changes = journal.preview_bulk_edit(&edit).unwrap();
+    assert_eq!(vec![1, 3], changes.iter().map(|change| change.line).collect::<Vec<_>>());
+    assert_eq!(
+        "3: [2018-08-16 10:00:00, 60] Review #wrok -> [2018-08-16 08:30:00, 60] Review #work",
+        changes[1].to_string()
+    );
+    assert_content!(journal_file, source);
+
+    assert_eq!(2, journal.bulk_edit(&edit).unwrap().len());
+    assert_content!(journal_file, r"[2018-08-16 07:30:00, 30] Standup #work
+# comment
+[2018-08-16 08:30:00, 60] Review #work
+[2018-08-17 09:00:00, 30] Standup #wrok
+");
+    assert!(journal.bulk_edit(&BulkEdit::new().retag("missing", "tag")).unwrap().is_empty());
+
+    create_file!(journal_file, source);
+    let mut journal = FileJournal::new(journal_file).with_header(Header::default());
+    let changes = journal.bulk_edit(&edit).unwrap();
+    assert_eq!(vec![1, 3], changes.iter().map(|change| change.line).collect::<Vec<_>>());
+    assert_content!(journal_file, r"#! version: 1
+[2018-08-16 07:30:00, 30] Standup #work
+# comment
+[2018-08-16 08:30:00, 60] Review #work
+[2018-08-17 09:00:00, 30] Standup #wrok
+");
+}
@@ -0,0 +1,85 @@
+use std::path::PathBuf;
+use chrono::{Local, Duration, NaiveDateTime, TimeZone};
+use file_assertions::{clear_dir, create_file, assert_content};
+use tt_core::{
+    idle::{IdleSource, IdleTracker, MockIdleSource, FileIdleSource},
+    journal::file::FileJournal,
+};
+
+fn time(source: &str) -> chrono::DateTime<Local> {
+    let naive = NaiveDateTime::parse_from_str(source, "%Y-%m-%d %H:%M:%S").unwrap();
+    Local.from_local_datetime(&naive).earliest().unwrap()
+}
+
+#[test]
+fn idle_to_rest() {
+    let journal_dir = &["target", "test_idle", "idle_to_rest"].iter().collect::<PathBuf>();
+    let journal_file = &journal_dir.join("journal.txt");
+    clear_dir!(journal_dir);
+    create_file!(journal_file, "[2018-08-16 08:00:00, 30] Standup\n[2018-08-16 09:00:00, ] Review\n");
+    let mut journal = FileJournal::new(journal_file);
+
+    let source = MockIdleSource::new(vec![
+        Duration::minutes(2),
+        Duration::minutes(10),
+        Duration::minutes(15),
+        Duration::seconds(5),
+        Duration::minutes(8),
+    ]);
+    let mut tracker = IdleTracker::new(source).with_threshold(Duration::minutes(5));
+
+    assert_eq!(None, tracker.poll(&mut journal, time("2018-08-16 09:30:00")).unwrap());
+    assert_eq!(Some(Duration::minutes(10)), tracker.poll(&mut journal, time("2018-08-16 09:40:00")).unwrap());
+    assert_content!(journal_file, "[2018-08-16 08:00:00, 30] Standup\n[2018-08-16 09:00:00,  (10)] Review\n");
+
+    assert_eq!(Some(Duration::minutes(5)), tracker.poll(&mut journal, time("2018-08-16 09:45:00")).unwrap());
+    assert_eq!(None, tracker.poll(&mut journal, time("2018-08-16 10:00:00")).unwrap());
+    assert_eq!(Some(Duration::minutes(8)), tracker.poll(&mut journal, time("2018-08-16 10:30:00")).unwrap());
+    assert_content!(journal_file, "[2018-08-16 08:00:00, 30] Standup\n[2018-08-16 09:00:00,  (23)] Review\n");
+}
+
+#[test]
+fn stopped_record() {
+    let journal_dir = &["target", "test_idle", "stopped_record"].iter().collect::<PathBuf>();
+    let journal_file = &journal_dir.join("journal.txt");
+    clear_dir!(journal_dir);
+    create_file!(journal_file, "[2018-08-16 09:00:00, 20] Review\n");
+    let mut journal = FileJournal::new(journal_file);
+
+    let mut tracker = IdleTracker::new(MockIdleSource::new(vec![Duration::minutes(10)]));
+    assert_eq!(None, tracker.poll(&mut journal, time("2018-08-16 09:40:00")).unwrap());
+    assert_content!(journal_file, "[2018-08-16 09:00:00, 20] Review\n");
+
+    let mut tracker = IdleTracker::new(MockIdleSource::new(vec![Duration::minutes(10)]));
+    assert_eq!(Some(Duration::minutes(5)), tracker.poll(&mut journal, time("2018-08-16 09:25:00")).unwrap());
+    assert_eq!(None, tracker.poll(&mut journal, time("2018-08-16 09:26:00")).unwrap());
+    assert_content!(journal_file, "[2018-08-16 09:00:00, 15 (5)] Review\n");
+}
+
+#[test]
+fn sub_minute_polls() {
+    let journal_dir = &["target", "test_idle", "sub_minute_polls"].iter().collect::<PathBuf>();
+    let journal_file = &journal_dir.join("journal.txt");
+    clear_dir!(journal_dir);
+    create_file!(journal_file, "[2018-08-16 09:00:00, ] Review\n");
+    let mut journal = FileJournal::new(journal_file);
+
+    let idle_times = (0..20).map(|poll| Duration::minutes(5) + Duration::seconds(30 * poll));
+    let mut tracker = IdleTracker::new(MockIdleSource::new(idle_times));
+    let mut total = Duration::zero();
+    for poll in 0..20 {
+        let now = time("2018-08-16 09:10:00") + Duration::seconds(30 * poll);
+        total += tracker.poll(&mut journal, now).unwrap().unwrap_or_else(Duration::zero);
+    }
+    assert_eq!(Duration::minutes(14), total);
+    assert_content!(journal_file, "[2018-08-16 09:00:00,  (14)] Review\n");
+}
+
+#[test]
+fn file_source() {
+    let idle_dir = &["target", "test_idle", "file_source"].iter().collect::<PathBuf>();
+    let idle_file = &idle_dir.join("idle");
+    clear_dir!(idle_dir);
+    create_file!(idle_file, "90000\n");
+    assert_eq!(Duration::seconds(90), FileIdleSource::new(idle_file).idle_time().unwrap());
+}
@@ -0,0 +1,80 @@
+#![cfg(feature = "json")]
+
+use std::path::PathBuf;
+use chrono::{Local, Duration, TimeZone};
+use file_assertions::{clear_dir, create_file, assert_content};
+use tt_core::{
+    record::{
+        Record,
+        RecordFieldType,
+    },
+    journal::{
+        Journal,
+        file::FileJournal,
+        json_lines::JsonLinesJournal,
+    },
+};
+
+#[test]
+fn add_get_update_remove() {
+    let journal_dir = &["target", "test_json_lines_journal", "crud"].iter().collect::<PathBuf>();
+    let journal_file = &journal_dir.join("journal.jsonl");
+    clear_dir!(journal_dir);
+    let mut journal = JsonLinesJournal::new(journal_file);
+
+    let record = Record {
+        start: Local.datetime_from_str("2018-08-16 13:52:43", Record::START_DATETIME_FORMAT).ok(),
+        activity: Some(Duration::minutes(42)),
+        rest: Some(Duration::minutes(-5)),
+        note: "Note 1".to_string(),
+    };
+    journal.add(&record).expect("Can't add record to journal");
+    journal.add(&Record { note: "Note 2".to_string(), ..Default::default() })
+        .expect("Can't add record to journal");
+    journal.add(&Record { activity: Some(Duration::minutes(85)), ..Default::default() })
+        .expect("Can't add record to journal");
+
+    assert_eq!(Some(&record), journal.get(&[], None).unwrap().as_ref());
+    assert_eq!("Note 2", journal.get(&[], Some(-2)).unwrap().unwrap().note);
+    assert_eq!(Some(Duration::minutes(85)), journal.get(&[], Some(-1)).unwrap().unwrap().activity);
+    assert_eq!(None, journal.get(&[], Some(3)).unwrap());
+    assert_eq!(Some(&record), journal.get(&[RecordFieldType::Note("Note 2".to_string())], Some(-1)).unwrap().as_ref());
+
+    assert!(journal.update(&[], Some(-1), |mut record| {
+        record.note = "Note 3".to_string();
+        Some(record)
+    }).unwrap());
+    assert!(journal.remove(&[RecordFieldType::Note("Note 2".to_string())], None, |_| true).unwrap());
+
+    let start = record.start.unwrap().to_rfc3339();
+    let expected = format!(
+        "{{\"start\":\"{}\",\"activity\":42,\"rest\":-5,\"note\":\"Note 1\"}}\n\
+         {{\"start\":null,\"activity\":85,\"rest\":null,\"note\":\"Note 3\"}}\n",
+        start
+    );
+    assert_content!(journal_file, expected);
+}
+
+#[test]
+fn convert_text() {
+    let journal_dir = &["target", "test_json_lines_journal", "convert"].iter().collect::<PathBuf>();
+    let text_file = &journal_dir.join("journal.txt");
+    let json_file = &journal_dir.join("journal.jsonl");
+    let back_file = &journal_dir.join("journal_back.txt");
+    clear_dir!(journal_dir);
+
+    let text = r"Day 1
+[2018-08-16 13:52:43, 42 (1)] Note 1
+[2018-08-16 15:40:25, 42 (-5)] Note 2
+[, 85]
+";
+    create_file!(text_file, text);
+
+    let mut journal = JsonLinesJournal::new(json_file);
+    assert_eq!(3, journal.import_text(&FileJournal::new(text_file)).unwrap());
+    assert_eq!("Note 2", journal.get(&[], Some(1)).unwrap().unwrap().note);
+
+    let mut back = FileJournal::new(back_file);
+    assert_eq!(3, journal.export_text(&mut back).unwrap());
+    assert_content!(back_file, text);
+}
@@ -0,0 +1,69 @@
+use std::path::PathBuf;
+use chrono::Duration;
+use file_assertions::{clear_dir, create_file, assert_content};
+use tt_core::{
+    record::{Record, RecordFieldType},
+    journal::{Journal, file::FileJournal, multi::MultiJournal, dedupe::Dedupe},
+    report::markdown,
+};
+
+#[test]
+fn merged_view() {
+    let journal_dir = &["target", "test_multi_journal", "merged_view"].iter().collect::<PathBuf>();
+    let alice_file = &journal_dir.join("alice.txt");
+    let bob_file = &journal_dir.join("bob.txt");
+    clear_dir!(journal_dir);
+    create_file!(alice_file, "[2018-08-16 09:00:00, 30] Standup\n[2018-08-16 11:00:00, 60] Review\n");
+    create_file!(bob_file, "[2018-08-16 10:00:00, 45] Deploy #ops\n# comment\n[2018-08-16 12:00:00, 15] Email\n");
+
+    let mut journal = MultiJournal::new("alice", FileJournal::new(alice_file))
+        .with_journal("bob", FileJournal::new(bob_file));
+    assert_eq!(vec!["alice", "bob"], journal.labels().collect::<Vec<_>>());
+
+    let labeled = journal.labeled_records().unwrap();
+    assert_eq!(
+        vec![("alice", "Standup"), ("bob", "Deploy #ops"), ("alice", "Review"), ("bob", "Email")],
+        labeled.iter().map(|l| (l.source.as_str(), l.record.note.as_str())).collect::<Vec<_>>()
+    );
+
+    let record = journal.get(&[], Some(-1)).unwrap().unwrap();
+    assert_eq!("Email", record.note);
+    let query = [RecordFieldType::Note("Deploy #ops".to_string())];
+    let labeled = journal.get_labeled(&query, Some(1)).unwrap().unwrap();
+    assert_eq!(("alice", "Review"), (labeled.source.as_str(), labeled.record.note.as_str()));
+    assert_eq!("Review #alice", labeled.tagged().note);
+
+    let report = markdown::render(journal.labeled_records().unwrap().into_iter().map(|l| l.tagged()));
+    assert!(report.contains("| 2018-08-16 | 10:00 | 10:45 | 0:45 |  | Deploy #ops #bob |"));
+    assert!(report.contains("| **Total** |  |  | **2:30** |"));
+
+    journal.add(&Record { note: "Added".to_string(), activity: Some(Duration::minutes(5)), ..Default::default() }).unwrap();
+    assert!(!journal.remove(&query, None, |_| true).unwrap());
+    assert_content!(bob_file, "[2018-08-16 10:00:00, 45] Deploy #ops\n# comment\n[2018-08-16 12:00:00, 15] Email\n");
+    assert_content!(alice_file, "[2018-08-16 09:00:00, 30] Standup\n[2018-08-16 11:00:00, 60] Review\n[, 5] Added\n");
+
+    assert!(!journal.remove(&[], Some(-1), |_| true).unwrap());
+    assert!(journal.update(&query, Some(1), |record| Some(Record { note: "Code review".to_string(), ..record })).unwrap());
+    assert_content!(bob_file, "[2018-08-16 10:00:00, 45] Deploy #ops\n# comment\n[2018-08-16 12:00:00, 15] Email\n");
+    assert_content!(alice_file, "[2018-08-16 09:00:00, 30] Standup\n[2018-08-16 11:00:00, 60] Code review\n[, 5] Added\n");
+
+    let journal = journal.with_primary("bob");
+    assert_eq!("bob", journal.primary_label());
+}
+
+#[test]
+fn dedupe_primary() {
+    let journal_dir = &["target", "test_multi_journal", "dedupe_primary"].iter().collect::<PathBuf>();
+    let alice_file = &journal_dir.join("alice.txt");
+    let bob_file = &journal_dir.join("bob.txt");
+    clear_dir!(journal_dir);
+    create_file!(alice_file, "[2018-08-16 09:00:00, 30] Standup\n[2018-08-16 09:00:00, 30] Standup\n");
+    create_file!(bob_file, "[2018-08-16 10:00:00, 45] Deploy\n[2018-08-16 10:00:00, 45] Deploy\n");
+
+    let mut journal = MultiJournal::new("alice", FileJournal::new(alice_file))
+        .with_journal("bob", FileJournal::new(bob_file));
+    let removed = Dedupe::new().apply(&mut journal).unwrap();
+    assert_eq!(vec!["Standup"], removed.iter().map(|record| record.note.as_str()).collect::<Vec<_>>());
+    assert_content!(alice_file, "[2018-08-16 09:00:00, 30] Standup\n");
+    assert_content!(bob_file, "[2018-08-16 10:00:00, 45] Deploy\n[2018-08-16 10:00:00, 45] Deploy\n");
+}
@@ -0,0 +1,93 @@
+use std::path::PathBuf;
+use chrono::{Local, Duration, NaiveDateTime, TimeZone};
+use file_assertions::{clear_dir, create_file, assert_content};
+use tt_core::{
+    pomodoro::{Cycle, Event, Phase, Pomodoro},
+    journal::file::FileJournal,
+};
+
+fn time(source: &str) -> chrono::DateTime<Local> {
+    let naive = NaiveDateTime::parse_from_str(source, "%Y-%m-%d %H:%M:%S").unwrap();
+    Local.from_local_datetime(&naive).earliest().unwrap()
+}
+
+#[test]
+fn pomodoro_cycle() {
+    let journal_dir = &["target", "test_pomodoro", "pomodoro_cycle"].iter().collect::<PathBuf>();
+    let journal_file = &journal_dir.join("journal.txt");
+    clear_dir!(journal_dir);
+    create_file!(journal_file, "[2018-08-16 08:00:00, 30] Standup\n");
+    let mut journal = FileJournal::new(journal_file);
+
+    let cycle = Cycle::new(Duration::minutes(25), Duration::minutes(5))
+        .and_then(|cycle| cycle.with_long_break(Duration::minutes(15), 2))
+        .unwrap();
+    let mut pomodoro = Pomodoro::new("Docs").with_cycle(cycle);
+
+    assert_eq!(Event::Started { at: time("2018-08-16 09:00:00") }, pomodoro.start(&mut journal, time("2018-08-16 09:00:00")).unwrap());
+    assert!(pomodoro.tick(&mut journal, time("2018-08-16 09:10:00")).unwrap().is_empty());
+    assert_eq!(Some(Phase::Work), pomodoro.phase());
+    assert_eq!(Some(Duration::minutes(15)), pomodoro.remaining(time("2018-08-16 09:10:00")));
+    assert_content!(journal_file, "[2018-08-16 08:00:00, 30] Standup\n[2018-08-16 09:00:00, 10] Docs\n");
+
+    assert_eq!(
+        vec![
+            Event::PhaseChanged { from: Phase::Work, to: Phase::ShortBreak, at: time("2018-08-16 09:25:00"), completed: 1 },
+            Event::PhaseChanged { from: Phase::ShortBreak, to: Phase::Work, at: time("2018-08-16 09:30:00"), completed: 1 },
+        ],
+        pomodoro.tick(&mut journal, time("2018-08-16 09:32:00")).unwrap()
+    );
+    assert_content!(journal_file, "[2018-08-16 08:00:00, 30] Standup\n[2018-08-16 09:00:00, 25 (5)] Docs\n[2018-08-16 09:30:00, 2] Docs\n");
+
+    assert_eq!(
+        vec![Event::PhaseChanged { from: Phase::Work, to: Phase::LongBreak, at: time("2018-08-16 09:55:00"), completed: 2 }],
+        pomodoro.tick(&mut journal, time("2018-08-16 10:00:00")).unwrap()
+    );
+    assert_eq!(
+        Some(Event::Stopped { at: time("2018-08-16 10:05:00"), completed: 2 }),
+        pomodoro.stop(&mut journal, time("2018-08-16 10:05:00")).unwrap()
+    );
+    assert!(!pomodoro.is_running());
+    assert_content!(journal_file, "[2018-08-16 08:00:00, 30] Standup\n[2018-08-16 09:00:00, 25 (5)] Docs\n[2018-08-16 09:30:00, 25 (10)] Docs\n");
+    assert_eq!(None, pomodoro.stop(&mut journal, time("2018-08-16 10:10:00")).unwrap());
+}
+
+#[test]
+fn gap_stops_session() {
+    let journal_dir = &["target", "test_pomodoro", "gap_stops_session"].iter().collect::<PathBuf>();
+    let journal_file = &journal_dir.join("journal.txt");
+    clear_dir!(journal_dir);
+    create_file!(journal_file, "");
+    let mut journal = FileJournal::new(journal_file);
+    let mut pomodoro = Pomodoro::new("Docs");
+
+    pomodoro.start(&mut journal, time("2018-08-16 09:00:00")).unwrap();
+    assert!(pomodoro.tick(&mut journal, time("2018-08-16 09:10:00")).unwrap().is_empty());
+    assert_eq!(
+        vec![Event::Stopped { at: time("2018-08-16 09:10:00"), completed: 0 }],
+        pomodoro.tick(&mut journal, time("2018-08-16 12:00:00")).unwrap()
+    );
+    assert!(!pomodoro.is_running());
+    assert_content!(journal_file, "[2018-08-16 09:00:00, 10] Docs\n");
+}
+
+#[test]
+fn sub_second_start() {
+    let journal_dir = &["target", "test_pomodoro", "sub_second_start"].iter().collect::<PathBuf>();
+    let journal_file = &journal_dir.join("journal.txt");
+    clear_dir!(journal_dir);
+    create_file!(journal_file, "");
+    let mut journal = FileJournal::new(journal_file);
+    let mut pomodoro = Pomodoro::new("Docs");
+    let start = time("2018-08-16 09:00:00") + Duration::milliseconds(123);
+
+    pomodoro.start(&mut journal, start).unwrap();
+    pomodoro.tick(&mut journal, start + Duration::seconds(90)).unwrap();
+    assert_content!(journal_file, "[2018-08-16 09:00:00, 1] Docs\n");
+    pomodoro.tick(&mut journal, start + Duration::seconds(150)).unwrap();
+    pomodoro.tick(&mut journal, start + Duration::minutes(10)).unwrap();
+    assert_content!(journal_file, "[2018-08-16 09:00:00, 10] Docs\n");
+
+    create_file!(journal_file, "");
+    assert!(pomodoro.tick(&mut journal, start + Duration::minutes(12)).is_err());
+}
@@ -0,0 +1,91 @@
+use std::path::PathBuf;
+use chrono::{Local, Duration, NaiveDateTime, TimeZone};
+use file_assertions::{clear_dir, create_file, assert_content};
+use tt_core::{
+    record::{Record, RecordFieldType},
+    journal::{Journal, sharded::ShardedJournal, dedupe::{Dedupe, Keep}},
+};
+
+fn record(start: &str, activity: i64, note: &str) -> Record {
+    Record {
+        start: NaiveDateTime::parse_from_str(start, Record::START_DATETIME_FORMAT).ok()
+            .and_then(|naive| Local.from_local_datetime(&naive).earliest()),
+        activity: Some(Duration::minutes(activity)),
+        rest: None,
+        note: note.to_string(),
+    }
+}
+
+#[test]
+fn add_routes_by_start() {
+    let root = &["target", "test_sharded_journal", "add"].iter().collect::<PathBuf>();
+    clear_dir!(root);
+    let august = &root.join("2018").join("08.txt");
+    let september = &root.join("2018").join("09.txt");
+    let january = &root.join("2019").join("01.txt");
+    let undated = &root.join("undated.txt");
+    let mut journal = ShardedJournal::new(root);
+
+    journal.add(&record("2018-08-31 23:00:00", 30, "Note 1")).unwrap();
+    journal.add(&record("2018-09-01 09:00:00", 15, "Note 2")).unwrap();
+    journal.add(&record("2019-01-02 10:00:00", 5, "Note 3")).unwrap();
+    journal.add(&Record { note: "Undated".to_string(), ..Default::default() }).unwrap();
+
+    assert_content!(august, "[2018-08-31 23:00:00, 30] Note 1\n");
+    assert_content!(september, "[2018-09-01 09:00:00, 15] Note 2\n");
+    assert_content!(january, "[2019-01-02 10:00:00, 5] Note 3\n");
+    assert_content!(undated, "[, ] Undated\n");
+    assert_eq!(4, journal.shards().unwrap().len());
+}
+
+#[test]
+fn offsets_span_shards() {
+    let root = &["target", "test_sharded_journal", "offsets"].iter().collect::<PathBuf>();
+    let august = &root.join("2018").join("08.txt");
+    let september = &root.join("2018").join("09.txt");
+    let other = &root.join("2018").join("notes.md");
+    clear_dir!(root);
+    create_file!(august, "[2018-08-30 09:00:00, 30] Note 1\n# comment\n[2018-08-31 09:00:00, 30] Note 2\n");
+    create_file!(september, "[2018-09-01 09:00:00, 15] Note 3\n");
+    create_file!(other, "not a shard\n");
+    let mut journal = ShardedJournal::new(root);
+
+    let note = |record: Option<Record>| record.map(|record| record.note);
+    assert_eq!(Some("Note 1".to_string()), note(journal.get(&[], None).unwrap()));
+    assert_eq!(Some("Note 3".to_string()), note(journal.get(&[], Some(-1)).unwrap()));
+    assert_eq!(Some("Note 2".to_string()), note(journal.get(&[], Some(-2)).unwrap()));
+    let query = [RecordFieldType::Note("Note 2".to_string())];
+    assert_eq!(Some("Note 3".to_string()), note(journal.get(&query, Some(1)).unwrap()));
+    assert_eq!(None, note(journal.get(&query, Some(2)).unwrap()));
+
+    assert!(journal.update(&[], Some(-1), |mut record| {
+        record.rest = Some(Duration::minutes(5));
+        Some(record)
+    }).unwrap());
+    assert!(journal.remove(&query, Some(-1), |_| true).unwrap());
+    assert_content!(august, "# comment\n[2018-08-31 09:00:00, 30] Note 2\n");
+    assert_content!(september, "[2018-09-01 09:00:00, 15 (5)] Note 3\n");
+
+    assert!(journal.update(&query, None, |mut record| {
+        record.start = record.start.map(|start| start + Duration::days(1));
+        Some(record)
+    }).unwrap());
+    assert_content!(august, "# comment\n");
+    assert_content!(september, "[2018-09-01 09:00:00, 15 (5)] Note 3\n[2018-09-01 09:00:00, 30] Note 2\n");
+}
+
+#[test]
+fn dedupe_across_shards() {
+    let root = &["target", "test_sharded_journal", "dedupe"].iter().collect::<PathBuf>();
+    clear_dir!(root);
+    let august = &root.join("2018").join("08.txt");
+    let september = &root.join("2018").join("09.txt");
+    create_file!(august, "[2018-08-16 09:00:00, 30] Standup\n# comment\n[2018-08-16 09:00:00, 30] Standup\n");
+    create_file!(september, "[2018-09-03 09:00:00, 15] Standup\n[2018-09-03 09:00:00, 15] Standup\n");
+    let mut journal = ShardedJournal::new(root);
+
+    let removed = Dedupe::new().with_keep(Keep::Newest).apply(&mut journal).unwrap();
+    assert_eq!(2, removed.len());
+    assert_content!(august, "# comment\n[2018-08-16 09:00:00, 30] Standup\n");
+    assert_content!(september, "[2018-09-03 09:00:00, 15] Standup\n");
+}